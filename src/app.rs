@@ -1,10 +1,28 @@
-use crate::todo::TodoItem;
+use crate::backup;
+use crate::config::{
+    Config, ConfirmLevel, DeleteFocus, EmptyEnter, StorageFormat, TagCombinator, UnsetPosition,
+};
+use crate::editor::TextArea;
+use crate::export::{self, ExportFormat};
+use crate::hooks;
+use crate::keymap::{Action, Keymap};
+use crate::lock::{Acquired, ListLock};
+use crate::logger;
+use crate::state::{AppState, ListView};
+use crate::todo::{DeletedTodo, Priority, Source, Status, TodoItem, is_context};
+use crate::watch::FileWatcher;
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use color_eyre::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
 use ratatui::widgets::ListState;
-use serde_json;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::hash::{DefaultHasher, Hasher};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tui_input::Input;
 use tui_input::backend::crossterm::EventHandler;
 
@@ -13,6 +31,398 @@ pub enum AppMode {
     Normal,
     Insert,
     Help,
+    /// Every key binding in a compact grid.
+    CheatSheet,
+    Confirm,
+    Search,
+    Dashboard,
+    Archive,
+    Detail,
+    EditNotes,
+    Tags,
+    Trash,
+    Prompt,
+    About,
+    Lists,
+    /// Groups of todos with the same text, found at startup.
+    Duplicates,
+}
+
+/// Single-line questions asked through the input box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt {
+    RecurUntil,
+    GotoId,
+    DueDate,
+    DueTime,
+    NewList,
+    RenameList,
+    Export,
+    /// Note for the todo with this id, which was just completed.
+    CompletionNote(usize),
+}
+
+impl Prompt {
+    pub fn title(self) -> &'static str {
+        match self {
+            Prompt::RecurUntil => " Repeat until (2026-05-01, fri, +3w; empty to clear) ",
+            Prompt::GotoId => " Go to id ",
+            Prompt::DueDate => " Due date (2026-05-01, today, fri, +3d; empty to clear) ",
+            Prompt::DueTime => " Due time (HH:MM, empty to clear) ",
+            Prompt::NewList => " New list name ",
+            Prompt::RenameList => " Rename list to ",
+            Prompt::Export => " Export the todos shown to (file.html, .md, .csv or todo.txt) ",
+            Prompt::CompletionNote(_) => " Completion note (Enter to skip) ",
+        }
+    }
+}
+
+/// Counts shown on the startup dashboard.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DaySummary {
+    pub overdue: usize,
+    pub due_today: usize,
+    pub completed_today: usize,
+    pub active: usize,
+}
+
+/// What happened since Monday, for the weekly review.
+#[derive(Debug, Default)]
+pub struct WeeklyReview<'a> {
+    /// First day of the week under review.
+    pub since: NaiveDate,
+    /// Todos completed this week, archived ones included, oldest first.
+    pub completed: Vec<&'a TodoItem>,
+    /// Open todos past their due date.
+    pub overdue: Vec<&'a TodoItem>,
+    /// Todos added this week, oldest first.
+    pub added: Vec<&'a TodoItem>,
+}
+
+/// A just deleted todo that can still be put back with a key press.
+#[derive(Debug, Clone)]
+pub struct UndoDelete {
+    pub id: usize,
+    pub text: String,
+    /// Where the todo was in the list.
+    index: usize,
+    until: Instant,
+}
+
+/// How long the undo offer stays up after a delete.
+const UNDO_WINDOW: Duration = Duration::from_secs(5);
+
+/// How long the strikethrough takes to sweep across a completed todo.
+const COMPLETION_ANIMATION: Duration = Duration::from_millis(300);
+
+/// Largest id accepted when loading; anything above is renumbered.
+const MAX_TODO_ID: usize = u32::MAX as usize;
+
+/// Percentage points the progress keys add or remove.
+const PROGRESS_STEP: i16 = 10;
+
+/// Maximum number of remembered search queries.
+const SEARCH_HISTORY_LIMIT: usize = 50;
+
+/// The list stored in `todos.json`, opened on startup.
+pub const DEFAULT_LIST_NAME: &str = "todos";
+
+/// File names in the data directory that hold something other than a list.
+const RESERVED_LIST_NAMES: [&str; 3] = ["archive", "trash", "state"];
+
+/// Characters that aren't allowed in list names since they become file names.
+const INVALID_LIST_NAME_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Filter {
+    All,
+    Active,
+    Completed,
+}
+
+impl Filter {
+    pub fn next(self) -> Self {
+        match self {
+            Filter::All => Filter::Active,
+            Filter::Active => Filter::Completed,
+            Filter::Completed => Filter::All,
+        }
+    }
+
+    pub fn matches(self, todo: &TodoItem) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Active => !todo.is_completed(),
+            Filter::Completed => todo.is_completed(),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Filter::All => "all",
+            Filter::Active => "active",
+            Filter::Completed => "completed",
+        }
+    }
+}
+
+/// Restricts the list to todos due within a window of time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DueWindow {
+    #[default]
+    Any,
+    Overdue,
+    Today,
+    /// From today through the coming Sunday.
+    ThisWeek,
+    NoDate,
+}
+
+impl DueWindow {
+    pub fn next(self) -> Self {
+        match self {
+            DueWindow::Any => DueWindow::Overdue,
+            DueWindow::Overdue => DueWindow::Today,
+            DueWindow::Today => DueWindow::ThisWeek,
+            DueWindow::ThisWeek => DueWindow::NoDate,
+            DueWindow::NoDate => DueWindow::Any,
+        }
+    }
+
+    pub fn matches(self, todo: &TodoItem, today: NaiveDate) -> bool {
+        match self {
+            DueWindow::Any => true,
+            DueWindow::Overdue => todo.is_overdue(today),
+            DueWindow::Today => todo.due == Some(today),
+            DueWindow::ThisWeek => {
+                let days_left = 6 - today.weekday().num_days_from_monday();
+                let end = today + chrono::Duration::days(days_left.into());
+                todo.due.is_some_and(|due| today <= due && due <= end)
+            }
+            DueWindow::NoDate => todo.due.is_none(),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DueWindow::Any => "any",
+            DueWindow::Overdue => "overdue",
+            DueWindow::Today => "today",
+            DueWindow::ThisWeek => "this week",
+            DueWindow::NoDate => "no date",
+        }
+    }
+}
+
+/// A search split into `#tags`, joined by `AND` or `OR`, an optional
+/// `source:<name>` and `+context`, and the remaining words, which must
+/// appear in the todo text. Tags match fuzzily: `#wrk` finds `#work`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub tags: Vec<String>,
+    pub combinator: TagCombinator,
+    pub source: Option<Source>,
+    /// Context to match, lowercased and with its sigil, e.g. `+work`.
+    pub context: Option<String>,
+}
+
+impl SearchQuery {
+    /// Parses search input. An `AND` or `OR` word overrides `combinator`;
+    /// matching is case-insensitive throughout. Words in double quotes are
+    /// always text, so `"AND"` or `"#1"` can be searched for.
+    pub fn parse(input: &str, combinator: TagCombinator) -> Self {
+        let mut query = Self {
+            text: String::new(),
+            tags: Vec::new(),
+            combinator,
+            source: None,
+            context: None,
+        };
+        let mut words = Vec::new();
+        let mut quoted = false;
+        for word in input.split_whitespace() {
+            // A quote runs until a word ending in one
+            if quoted || word.starts_with('"') {
+                let text = if quoted { word } else { &word[1..] };
+                quoted = !text.ends_with('"');
+                let text = text.strip_suffix('"').unwrap_or(text);
+                if !text.is_empty() {
+                    words.push(text.to_lowercase());
+                }
+                continue;
+            }
+            let source = word.strip_prefix("source:").and_then(Source::parse);
+            match word {
+                "AND" => query.combinator = TagCombinator::And,
+                "OR" => query.combinator = TagCombinator::Or,
+                _ if source.is_some() => query.source = source,
+                _ if is_context(word) => query.context = Some(word.to_lowercase()),
+                _ => match word.strip_prefix('#') {
+                    Some(tag) if !tag.is_empty() => query.tags.push(tag.to_lowercase()),
+                    _ => words.push(word.to_lowercase()),
+                },
+            }
+        }
+        query.text = words.join(" ");
+        query
+    }
+
+    pub fn matches(&self, todo: &TodoItem) -> bool {
+        let has_tag = |tag: &String| {
+            todo.tags
+                .iter()
+                .any(|t| is_subsequence(tag, &t.to_lowercase()))
+        };
+        let tags_match = self.tags.is_empty()
+            || match self.combinator {
+                TagCombinator::And => self.tags.iter().all(has_tag),
+                TagCombinator::Or => self.tags.iter().any(has_tag),
+            };
+        tags_match
+            && self.source.is_none_or(|source| todo.source == Some(source))
+            && self.context.as_ref().is_none_or(|context| {
+                todo.context
+                    .as_ref()
+                    .is_some_and(|c| c.to_lowercase() == *context)
+            })
+            && todo.text.to_lowercase().contains(&self.text)
+    }
+
+    /// The tag part of the query for display, e.g. `#work AND #urgent`.
+    pub fn tag_expression(&self) -> Option<String> {
+        if self.tags.is_empty() {
+            return None;
+        }
+        let separator = format!(" {} ", self.combinator.label());
+        Some(
+            self.tags
+                .iter()
+                .map(|tag| format!("#{}", tag))
+                .collect::<Vec<_>>()
+                .join(&separator),
+        )
+    }
+}
+
+/// How the list is ordered. Sorting rewrites the order the todos are
+/// stored in, so it replaces any order made by moving todos by hand;
+/// going back to `Created` ascending restores creation order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortMode {
+    #[default]
+    Created,
+    Text,
+    Due,
+    Priority,
+    Completion,
+    ByTag,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Created => SortMode::Text,
+            SortMode::Text => SortMode::Due,
+            SortMode::Due => SortMode::Priority,
+            SortMode::Priority => SortMode::Completion,
+            SortMode::Completion => SortMode::ByTag,
+            SortMode::ByTag => SortMode::Created,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Created => "created",
+            SortMode::Text => "text",
+            SortMode::Due => "due",
+            SortMode::Priority => "priority",
+            SortMode::Completion => "done",
+            SortMode::ByTag => "tag",
+        }
+    }
+
+    /// Ascending order for this mode, with the highest priority first.
+    /// Todos without a tag sort after the others; those without a due date
+    /// or priority are placed by [`SortMode::unset_position`].
+    fn compare(self, a: &TodoItem, b: &TodoItem) -> Ordering {
+        match self {
+            SortMode::Created => a.id.cmp(&b.id),
+            SortMode::Text => a.text.to_lowercase().cmp(&b.text.to_lowercase()),
+            SortMode::Due => a.due.cmp(&b.due),
+            SortMode::Priority => b.priority.cmp(&a.priority),
+            SortMode::Completion => a.status.cmp(&b.status),
+            SortMode::ByTag => {
+                let key = |t: &TodoItem| {
+                    let tag = t.primary_tag().map(str::to_lowercase);
+                    (tag.is_none(), tag)
+                };
+                key(a).cmp(&key(b))
+            }
+        }
+    }
+
+    /// Where `todo` goes if it has no value to sort by in this mode, which
+    /// stays the same whichever way the sort runs.
+    fn unset_position(self, todo: &TodoItem, config: &Config) -> Option<UnsetPosition> {
+        match self {
+            SortMode::Due if todo.due.is_none() => Some(config.no_due_position),
+            SortMode::Priority if todo.priority.is_none() => Some(config.no_priority_position),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl SortDirection {
+    pub fn reversed(self) -> Self {
+        match self {
+            SortDirection::Ascending => SortDirection::Descending,
+            SortDirection::Descending => SortDirection::Ascending,
+        }
+    }
+
+    pub fn arrow(self) -> &'static str {
+        match self {
+            SortDirection::Ascending => "↑",
+            SortDirection::Descending => "↓",
+        }
+    }
+}
+
+/// An action waiting for confirmation. Todos are kept by id, since the list
+/// can be reloaded from disk while the prompt is up.
+#[derive(Debug, Clone, Copy)]
+pub enum PendingAction {
+    ClearCompleted { count: usize },
+    UncompleteRecurring { id: usize },
+    Delete { id: usize },
+    Reload,
+    Quit,
+}
+
+impl PendingAction {
+    /// The lowest `confirm_level` at which this action asks first.
+    fn confirm_level(self) -> ConfirmLevel {
+        match self {
+            PendingAction::Quit => ConfirmLevel::All,
+            _ => ConfirmLevel::Destructive,
+        }
+    }
+
+    /// The todo the action is about, if any.
+    fn todo_id(self) -> Option<usize> {
+        match self {
+            PendingAction::UncompleteRecurring { id } | PendingAction::Delete { id } => Some(id),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -21,35 +431,559 @@ pub struct App {
     pub list_state: ListState,
     pub mode: AppMode,
     pub input: Input,
+    /// Whether Insert mode stays open after adding a todo.
+    pub rapid_entry: bool,
+    /// Todo that todos added in Insert mode become subtasks of.
+    pub new_parent: Option<usize>,
     next_id: usize,
     pub should_quit: bool,
+    pub pending_action: Option<PendingAction>,
+    pub dense: bool,
+    pub filter: Filter,
+    pub due_window: DueWindow,
+    /// Whether completed todos are folded into a single summary line.
+    pub collapse_completed: bool,
+    /// The filter and due window to return to while the overdue view is on.
+    pub overdue_view: Option<(Filter, DueWindow)>,
+    pub config: Config,
+    pub keymap: Keymap,
+    pub search: Input,
+    /// Latest known terminal size as (width, height).
+    pub terminal_size: (u16, u16),
+    /// Position in `state.search_history` while browsing it with Up/Down.
+    history_index: Option<usize>,
+    pub notes_editor: TextArea,
+    pub prompt: Option<Prompt>,
+    /// Why the prompt's input was rejected, until it is edited.
+    pub prompt_error: Option<String>,
+    /// Lines scrolled past at the top of the help popup or cheat sheet.
+    pub help_scroll: u16,
+    /// One-off feedback shown in the status bar until the next key press.
+    pub message: Option<String>,
+    /// The last deleted todo while it can still be undone.
+    pub undo_delete: Option<UndoDelete>,
+    /// When the strikethrough started sweeping across todos completed just
+    /// now, by id.
+    completion_animations: HashMap<usize, Instant>,
+    /// Whether there are changes that have not been written to disk yet.
+    pub dirty: bool,
+    /// Whether the list was backed up before its first save this session.
+    backed_up: bool,
+    pub last_saved: Option<DateTime<Local>>,
+    /// When changes held back by `save_delay_ms` are due to be written.
+    save_due: Option<Instant>,
+    /// When the last terminal event arrived, for the idle timeout.
+    pub last_activity: Instant,
+    /// Todos falling due after this moment have not been notified yet.
+    due_checked_at: NaiveDateTime,
+    pub tag_state: ListState,
+    pub archive: Vec<TodoItem>,
+    pub archive_state: ListState,
+    /// Recently deleted todos, newest first.
+    pub trash: Vec<DeletedTodo>,
+    pub trash_state: ListState,
+    /// Ids of todos sharing the same text, one group per text, in list order.
+    pub duplicates: Vec<Vec<usize>>,
+    pub duplicates_state: ListState,
+    /// List names shown in the list picker.
+    pub lists: Vec<String>,
+    pub lists_state: ListState,
+    list_name: String,
+    /// The list open before the current one, for flipping between two.
+    previous_list: Option<String>,
+    watcher: Option<FileWatcher>,
+    /// Held while the TUI has the list open; `None` for one-off commands.
+    lock: Option<ListLock>,
+    /// Whether lists are locked as they are opened.
+    locking: bool,
+    /// Set when another instance has the list open, so nothing is saved.
+    pub read_only: bool,
+    /// Hash of the list file as last read or written, to tell other
+    /// programs' changes apart from our own.
+    data_hash: Option<u64>,
+    state: AppState,
+    state_file: PathBuf,
+    archive_file: PathBuf,
+    trash_file: PathBuf,
     data_file: String,
 }
 
 impl App {
-    pub fn new() -> Result<Self> {
-        let data_file = Self::get_data_file_path()?;
-        let todos = Self::load_todos(&data_file)?;
+    /// Opens `list`, or the configured `default_list`, falling back to the
+    /// default list with a message when the name can't be used. A list that
+    /// doesn't exist yet starts out empty. With `lock`, the list is locked
+    /// before anything is written to it, see [`App::lock_list`].
+    pub fn new(list: Option<&str>, lock: bool) -> Result<Self> {
+        let config = Config::load()?;
+        let mut data_file = Self::get_data_file_path(config.storage_format)?;
+        let mut warning = None;
+        if let Some(name) = list.or(config.default_list.as_deref()) {
+            match Self::check_list_name(name) {
+                Ok(()) => {
+                    let file = format!("{}.{}", name, config.storage_format.extension());
+                    data_file = Path::new(&data_file)
+                        .with_file_name(file)
+                        .to_string_lossy()
+                        .to_string();
+                }
+                Err(err) => {
+                    logger::debug!("can't open list {:?}: {}", name, err);
+                    warning = Some(format!("{}, opened '{}' instead", err, DEFAULT_LIST_NAME));
+                }
+            }
+        }
+        let state_file = Path::new(&data_file).with_file_name("state.json");
+        let mut state = AppState::load(&state_file)?;
+        let today = Local::now().date_naive();
+        let legacy_opened = state.last_opened;
+        let view = state
+            .lists
+            .entry(Self::list_name_of(&data_file))
+            .or_default();
+        let (todos, changed) =
+            Self::read_todos(&data_file, view.last_opened.or(legacy_opened), today)?;
+        view.last_opened = Some(today);
+        state.last_opened = Some(today);
+        state.save(&state_file)?;
+
+        let mut app = Self::from_parts(todos, config, state, state_file, data_file);
+        app.message = warning;
+        app.archive = Self::load_list(&app.archive_file)?;
+        app.trash = Self::load_list(&app.trash_file)?;
+        app.data_hash = file_hash(Path::new(&app.data_file));
+        if lock {
+            app.lock_list()?;
+        }
+        // Write a newly named list straight away so it shows up as a list
+        if (changed || !Path::new(&app.data_file).exists()) && !app.read_only {
+            app.save_todos()?;
+        }
+        if app.config.watch_file {
+            let dir = Path::new(&app.data_file).parent().unwrap_or(Path::new("."));
+            match FileWatcher::new(dir) {
+                Ok(watcher) => app.watcher = Some(watcher),
+                Err(err) => logger::debug!("could not watch {}: {}", dir.display(), err),
+            }
+        }
+
+        // The instance holding the list does the archiving
+        if !app.read_only {
+            let archived = app.auto_archive(Utc::now());
+            if archived > 0 {
+                logger::debug!("auto-archived {} completed todos", archived);
+            }
+        }
+        if app.config.report_duplicates {
+            app.open_duplicates();
+        }
+
+        Ok(app)
+    }
+
+    fn from_parts(
+        todos: Vec<TodoItem>,
+        config: Config,
+        state: AppState,
+        state_file: PathBuf,
+        data_file: String,
+    ) -> Self {
         let next_id = todos.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+        let ext = StorageFormat::for_path(Path::new(&data_file)).extension();
+        let archive_file = Path::new(&data_file).with_file_name(format!("archive.{}", ext));
+        let trash_file = Path::new(&data_file).with_file_name(format!("trash.{}", ext));
+        let list_name = Self::list_name_of(&data_file);
+        let filter = state
+            .lists
+            .get(&list_name)
+            .and_then(|view| view.filter)
+            .unwrap_or(Self::default_filter(&config));
+        let mode = if config.insert_on_empty && todos.is_empty() {
+            AppMode::Insert
+        } else if config.show_dashboard {
+            AppMode::Dashboard
+        } else {
+            AppMode::Normal
+        };
 
         let mut app = Self {
             todos,
             list_state: ListState::default(),
-            mode: AppMode::Normal,
+            mode,
             input: Input::default(),
+            rapid_entry: false,
+            new_parent: None,
             next_id,
             should_quit: false,
+            pending_action: None,
+            dense: false,
+            filter,
+            due_window: DueWindow::default(),
+            collapse_completed: false,
+            overdue_view: None,
+            config,
+            keymap: Keymap::default(),
+            search: Input::default(),
+            terminal_size: (0, 0),
+            history_index: None,
+            notes_editor: TextArea::default(),
+            prompt: None,
+            prompt_error: None,
+            help_scroll: 0,
+            message: None,
+            undo_delete: None,
+            completion_animations: HashMap::new(),
+            dirty: false,
+            backed_up: false,
+            last_saved: None,
+            save_due: None,
+            last_activity: Instant::now(),
+            due_checked_at: Local::now().naive_local(),
+            tag_state: ListState::default(),
+            archive: Vec::new(),
+            archive_state: ListState::default(),
+            trash: Vec::new(),
+            trash_state: ListState::default(),
+            duplicates: Vec::new(),
+            duplicates_state: ListState::default(),
+            lists: Vec::new(),
+            lists_state: ListState::default(),
+            list_name,
+            previous_list: None,
+            watcher: None,
+            lock: None,
+            locking: false,
+            read_only: false,
+            data_hash: None,
+            state,
+            state_file,
+            archive_file,
+            trash_file,
             data_file,
         };
 
-        if !app.todos.is_empty() {
+        app.sink_completed();
+        if !app.visible_indices().is_empty() {
             app.list_state.select(Some(0));
         }
 
-        Ok(app)
+        app
+    }
+
+    /// Gives todos with a duplicate or out-of-range id a fresh one, in file
+    /// order after the highest valid id. The first todo holding an id keeps
+    /// it. Returns how many todos were renumbered.
+    fn normalize_ids(todos: &mut [TodoItem]) -> usize {
+        let valid = |id: usize| (1..=MAX_TODO_ID).contains(&id);
+
+        let mut next_id = todos
+            .iter()
+            .map(|t| t.id)
+            .filter(|&id| valid(id))
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let mut seen = HashSet::new();
+        let mut renumbered = 0;
+        for todo in todos.iter_mut() {
+            if !valid(todo.id) || !seen.insert(todo.id) {
+                todo.id = next_id;
+                next_id += 1;
+                renumbered += 1;
+            }
+        }
+        renumbered
+    }
+
+    /// Marks recurring todos as incomplete again when the app is opened on a
+    /// later calendar day than last time. Returns whether anything changed.
+    fn reset_recurring(
+        todos: &mut [TodoItem],
+        last_opened: Option<NaiveDate>,
+        today: NaiveDate,
+    ) -> bool {
+        if last_opened.is_none_or(|last| last >= today) {
+            return false;
+        }
+
+        let mut changed = false;
+        for todo in todos
+            .iter_mut()
+            .filter(|t| t.recurs_on(today) && t.is_completed())
+        {
+            todo.toggle_completion();
+            changed = true;
+        }
+        changed
+    }
+
+    /// Reads a list's todos, renumbering bad ids and resetting recurring
+    /// todos completed before today. Returns whether anything was changed.
+    fn read_todos(
+        file_path: &str,
+        last_opened: Option<NaiveDate>,
+        today: NaiveDate,
+    ) -> Result<(Vec<TodoItem>, bool)> {
+        let mut todos = Self::load_todos(file_path)?;
+        logger::debug!("loaded {} todos from {}", todos.len(), file_path);
+        let renumbered = Self::normalize_ids(&mut todos);
+        if renumbered > 0 {
+            logger::debug!(
+                "renumbered {} todos with duplicate or invalid ids",
+                renumbered
+            );
+        }
+        let reset = Self::reset_recurring(&mut todos, last_opened, today);
+        if reset {
+            logger::debug!("reset recurring todos for {}", today);
+        }
+        Ok((todos, reset || renumbered > 0))
+    }
+
+    fn default_filter(config: &Config) -> Filter {
+        if config.hide_completed {
+            Filter::Active
+        } else {
+            Filter::All
+        }
+    }
+
+    /// Lists are named after their file, so `todos.json` is the `todos` list.
+    fn list_name_of(data_file: &str) -> String {
+        Path::new(data_file)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn list_name(&self) -> &str {
+        &self.list_name
+    }
+
+    fn list_file(&self, name: &str) -> PathBuf {
+        let ext = StorageFormat::for_path(Path::new(&self.data_file)).extension();
+        Path::new(&self.data_file).with_file_name(format!("{}.{}", name, ext))
+    }
+
+    /// Names of all lists in the data directory, including the current one.
+    fn list_names(&self) -> Vec<String> {
+        let ext = StorageFormat::for_path(Path::new(&self.data_file)).extension();
+        let dir = Path::new(&self.data_file)
+            .parent()
+            .unwrap_or_else(|| Path::new("."));
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == ext))
+            .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+            .filter(|name| !RESERVED_LIST_NAMES.contains(&name.as_str()))
+            .collect();
+        if !names.contains(&self.list_name) {
+            names.push(self.list_name.clone());
+        }
+        names.sort();
+        names
+    }
+
+    fn validate_list_name(&self, name: &str) -> Result<()> {
+        Self::check_list_name(name)?;
+        if self.list_file(name).exists() {
+            return Err(color_eyre::eyre::eyre!(
+                "A list named '{}' already exists",
+                name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that `name` can be used for a list's file.
+    fn check_list_name(name: &str) -> Result<()> {
+        if name.is_empty() {
+            return Err(color_eyre::eyre::eyre!("List names can't be empty"));
+        }
+        if name.starts_with('.')
+            || name
+                .chars()
+                .any(|c| c.is_control() || INVALID_LIST_NAME_CHARS.contains(&c))
+        {
+            return Err(color_eyre::eyre::eyre!(
+                "'{}' can't be used as a file name",
+                name
+            ));
+        }
+        if RESERVED_LIST_NAMES.contains(&name) {
+            return Err(color_eyre::eyre::eyre!("'{}' is a reserved name", name));
+        }
+        Ok(())
+    }
+
+    pub fn open_lists(&mut self) {
+        self.lists = self.list_names();
+        let current = self.lists.iter().position(|name| *name == self.list_name);
+        self.lists_state.select(current);
+        self.mode = AppMode::Lists;
+    }
+
+    /// Switches to the list highlighted in the list picker.
+    pub fn switch_to_selected_list(&mut self) {
+        if let Some(name) = self
+            .lists_state
+            .selected()
+            .and_then(|selected| self.lists.get(selected))
+            .cloned()
+        {
+            match self.switch_list(&name) {
+                Ok(()) => self.mode = AppMode::Normal,
+                Err(err) => self.message = Some(format!("Could not open '{}': {}", name, err)),
+            }
+        }
+    }
+
+    /// Goes back to the list that was open before the current one.
+    pub fn switch_to_previous_list(&mut self) {
+        let Some(name) = self.previous_list.clone() else {
+            self.message = Some("No previous list".to_string());
+            return;
+        };
+        if let Err(err) = self.switch_list(&name) {
+            self.message = Some(format!("Could not open '{}': {}", name, err));
+        }
+    }
+
+    /// Loads another list in place of the current one, restoring the sort
+    /// and filter it was last viewed with.
+    pub fn switch_list(&mut self, name: &str) -> Result<()> {
+        if name == self.list_name {
+            return Ok(());
+        }
+        self.flush()?;
+
+        let file = self.list_file(name).to_string_lossy().to_string();
+        let today = Local::now().date_naive();
+        let last_opened = self.state.lists.get(name).and_then(|view| view.last_opened);
+        let (todos, changed) = Self::read_todos(&file, last_opened, today)?;
+        // Lock before changing anything, so a failure leaves the app on the
+        // list it was on
+        let acquired = match self.locking {
+            true => Some(ListLock::acquire(Path::new(&file))?),
+            false => None,
+        };
+        let view = self.state.lists.entry(name.to_string()).or_default();
+        view.last_opened = Some(today);
+        let filter = view.filter;
+
+        self.next_id = todos.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+        self.todos = todos;
+        self.sink_completed();
+        self.data_file = file;
+        self.data_hash = file_hash(Path::new(&self.data_file));
+        self.previous_list = Some(std::mem::replace(&mut self.list_name, name.to_string()));
+        self.filter = filter.unwrap_or(Self::default_filter(&self.config));
+        if let Some((_, due_window)) = self.overdue_view.take() {
+            self.due_window = due_window;
+        }
+        self.search.reset();
+        self.undo_delete = None;
+        self.dirty = false;
+        self.backed_up = false;
+        self.last_saved = None;
+        self.select_first_visible();
+        logger::debug!("switched to list {}", name);
+        if let Some(acquired) = acquired {
+            self.take_lock(acquired);
+        }
+
+        if changed && !self.read_only {
+            self.save_todos()?;
+        }
+        self.save_state()
+    }
+
+    /// Locks the current list against other instances, and locks each list
+    /// switched to from now on. When another instance already has the list
+    /// open, it is opened read-only instead.
+    pub fn lock_list(&mut self) -> Result<()> {
+        self.locking = true;
+        // Let go of the previous list first, since taking the lock again
+        // hands back the same file, which dropping the old one would remove
+        self.lock = None;
+        self.read_only = false;
+        let acquired = ListLock::acquire(Path::new(&self.data_file))?;
+        self.take_lock(acquired);
+        Ok(())
+    }
+
+    /// Holds a lock just taken on the current list, or opens the list
+    /// read-only when another instance has it.
+    fn take_lock(&mut self, acquired: Acquired) {
+        self.read_only = false;
+        match acquired {
+            Acquired::Locked(lock) => self.lock = Some(lock),
+            Acquired::HeldBy(pid) => {
+                logger::debug!("{} is locked by pid {}", self.data_file, pid);
+                self.lock = None;
+                self.read_only = true;
+                self.message = Some(format!(
+                    "'{}' is open in another oxitodo (pid {}), changes won't be saved",
+                    self.list_name, pid
+                ));
+            }
+        }
+    }
+
+    /// Creates an empty list and switches to it.
+    fn create_list(&mut self, name: &str) -> Result<()> {
+        self.validate_list_name(name)?;
+        self.write_todos::<TodoItem>(&self.list_file(name), &[])?;
+        self.switch_list(name)
+    }
+
+    /// Renames the list highlighted in the list picker, moving its file and
+    /// its saved preferences.
+    fn rename_selected_list(&mut self, new_name: &str) -> Result<()> {
+        let Some(old_name) = self
+            .lists_state
+            .selected()
+            .and_then(|selected| self.lists.get(selected))
+            .cloned()
+        else {
+            return Ok(());
+        };
+        if new_name == old_name {
+            return Ok(());
+        }
+        self.validate_list_name(new_name)?;
+
+        let old_file = self.list_file(&old_name);
+        let new_file = self.list_file(new_name);
+        // The current list may not have been written yet
+        if old_file.exists() {
+            fs::rename(&old_file, &new_file)?;
+        }
+        if let Some(view) = self.state.lists.remove(&old_name) {
+            self.state.lists.insert(new_name.to_string(), view);
+        }
+        if self.previous_list.as_ref() == Some(&old_name) {
+            self.previous_list = Some(new_name.to_string());
+        }
+        if old_name == self.list_name {
+            self.list_name = new_name.to_string();
+            self.data_file = new_file.to_string_lossy().to_string();
+            if self.locking && !self.read_only {
+                self.lock_list()?;
+            }
+        }
+        logger::debug!("renamed list {} to {}", old_name, new_name);
+        self.save_state()?;
+
+        self.lists = self.list_names();
+        let renamed = self.lists.iter().position(|name| name == new_name);
+        self.lists_state.select(renamed);
+        Ok(())
     }
 
-    fn get_data_file_path() -> Result<String> {
+    pub fn get_data_file_path(format: StorageFormat) -> Result<String> {
         // Try XDG_DATA_HOME first, fall back to ~/.local/share
         let data_dir = if let Ok(xdg_data_home) = std::env::var("XDG_DATA_HOME") {
             PathBuf::from(xdg_data_home).join("oxitodo")
@@ -68,144 +1002,3412 @@ impl App {
             fs::create_dir_all(&data_dir)?;
         }
 
-        let data_file = data_dir.join("todos.json");
+        let data_file = data_dir.join(format!("{}.{}", DEFAULT_LIST_NAME, format.extension()));
         Ok(data_file.to_string_lossy().to_string())
     }
 
-    fn load_todos(file_path: &str) -> Result<Vec<TodoItem>> {
-        if Path::new(file_path).exists() {
+    pub fn load_todos(file_path: &str) -> Result<Vec<TodoItem>> {
+        Self::load_list(Path::new(file_path))
+    }
+
+    /// Reads a list in the format given by the file's extension.
+    pub fn load_list<T: DeserializeOwned>(file_path: &Path) -> Result<Vec<T>> {
+        if file_path.exists() {
             let content = fs::read_to_string(file_path)?;
-            let todos: Vec<TodoItem> = serde_json::from_str(&content)?;
-            Ok(todos)
+            StorageFormat::for_path(file_path).deserialize(&content)
         } else {
             Ok(vec![])
         }
     }
 
-    fn save_todos(&self) -> Result<()> {
-        let json = serde_json::to_string_pretty(&self.todos)?;
-        fs::write(&self.data_file, json)?;
-        Ok(())
+    /// Writes anything not saved yet, before exiting.
+    pub fn flush(&mut self) -> Result<()> {
+        if self.dirty && !self.read_only {
+            self.write_list()?;
+        }
+        self.save_state()
     }
 
-    pub fn add_todo(&mut self, text: String) {
-        if !text.trim().is_empty() {
-            let todo = TodoItem::new(self.next_id, text.trim().to_string());
-            self.todos.push(todo);
-            self.next_id += 1;
+    /// Writes the todos to disk, or with `save_delay_ms` once the delay
+    /// has passed. Callers have just changed the list, so it stays marked
+    /// dirty until it is written.
+    fn save_todos(&mut self) -> Result<()> {
+        self.dirty = true;
+        if self.config.auto_sort && self.is_sorted() {
+            self.sort_todos();
+        }
+        if self.read_only {
+            return Err(color_eyre::eyre::eyre!("'{}' is read-only", self.list_name));
+        }
+        if self.config.save_delay_ms > 0 {
+            // The first change sets the time, so steady typing can't put
+            // the write off for good
+            let delay = Duration::from_millis(self.config.save_delay_ms);
+            self.save_due.get_or_insert(Instant::now() + delay);
+            return Ok(());
+        }
+        self.write_list()
+    }
 
-            // Select the new item
-            self.list_state.select(Some(self.todos.len() - 1));
+    /// Writes the todos to disk straight away.
+    fn write_list(&mut self) -> Result<()> {
+        self.save_due = None;
+        self.back_up();
+        self.write_todos(Path::new(&self.data_file), &self.todos)?;
+        self.data_hash = file_hash(Path::new(&self.data_file));
+        self.dirty = false;
+        self.last_saved = Some(Local::now());
+        Ok(())
+    }
 
-            let _ = self.save_todos();
+    /// Backs up the list file the first time it is about to be overwritten
+    /// this session. A failed backup is logged but doesn't stop the save.
+    fn back_up(&mut self) {
+        let data_file = Path::new(&self.data_file);
+        if self.backed_up || self.config.backup_count == 0 || !data_file.exists() {
+            return;
+        }
+        self.backed_up = true;
+        if let Err(err) = backup::create(
+            data_file,
+            self.config.backup_count,
+            self.config.backup_compress,
+        ) {
+            logger::debug!("could not back up {}: {}", self.data_file, err);
         }
     }
 
-    pub fn toggle_current_todo(&mut self) {
-        if let Some(selected) = self.list_state.selected() {
-            if let Some(todo) = self.todos.get_mut(selected) {
-                todo.toggle_completion();
-                let _ = self.save_todos();
-            }
-        }
+    /// Backups of the current list, oldest first.
+    pub fn backups(&self) -> Vec<PathBuf> {
+        backup::list(Path::new(&self.data_file))
     }
 
-    pub fn delete_current_todo(&mut self) {
-        if let Some(selected) = self.list_state.selected() {
-            if selected < self.todos.len() {
-                self.todos.remove(selected);
+    /// Replaces the list with a backup, given as a path or a file name in
+    /// the backup directory. Returns how many todos it held.
+    pub fn restore_backup(&mut self, name: &str) -> Result<usize> {
+        let path = backup::find(Path::new(&self.data_file), name)?;
+        let content = backup::read(&path)?;
+        // The format is that of the name without the `.gz`
+        let unpacked = if path.extension().is_some_and(|ext| ext == "gz") {
+            path.with_extension("")
+        } else {
+            path.clone()
+        };
+        let mut todos: Vec<TodoItem> = StorageFormat::for_path(&unpacked).deserialize(&content)?;
+        Self::normalize_ids(&mut todos);
 
-                // Adjust selection
-                if self.todos.is_empty() {
-                    self.list_state.select(None);
-                } else if selected >= self.todos.len() {
-                    self.list_state.select(Some(self.todos.len() - 1));
-                }
+        self.next_id = todos.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+        self.todos = todos;
+        self.sink_completed();
+        self.save_todos()?;
+        logger::debug!("restored {} from {}", self.data_file, path.display());
+        Ok(self.todos.len())
+    }
 
-                let _ = self.save_todos();
-            }
+    fn save_archive(&self) -> Result<()> {
+        if self.read_only {
+            return Ok(());
         }
+        self.write_todos(&self.archive_file, &self.archive)
     }
 
-    pub fn next_item(&mut self) {
-        if self.todos.is_empty() {
-            return;
+    fn save_trash(&self) -> Result<()> {
+        if self.read_only {
+            return Ok(());
         }
+        self.write_todos(&self.trash_file, &self.trash)
+    }
 
-        let selected = match self.list_state.selected() {
-            Some(i) => {
-                if i >= self.todos.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
-        };
-        self.list_state.select(Some(selected));
+    fn write_todos<T: Serialize>(&self, file_path: &Path, items: &[T]) -> Result<()> {
+        let content =
+            StorageFormat::for_path(file_path).serialize(items, self.config.compact_storage)?;
+        fs::write(file_path, content).inspect_err(|err| {
+            logger::debug!("failed to save {}: {}", file_path.display(), err);
+        })?;
+        logger::debug!("saved {} items to {}", items.len(), file_path.display());
+        Ok(())
     }
 
-    pub fn previous_item(&mut self) {
-        if self.todos.is_empty() {
+    /// Keeps deleted todos in the recycle bin, dropping the oldest ones
+    /// beyond `trash_limit`.
+    fn move_to_trash(&mut self, todos: Vec<TodoItem>) {
+        if todos.is_empty() {
             return;
         }
 
-        let selected = match self.list_state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.todos.len() - 1
-                } else {
-                    i - 1
+        // Subtasks of deleted todos move up to the deleted todo's parent
+        for deleted in &todos {
+            for todo in &mut self.todos {
+                if todo.parent == Some(deleted.id) {
+                    todo.parent = deleted.parent;
                 }
             }
-            None => 0,
-        };
-        self.list_state.select(Some(selected));
-    }
+        }
 
-    pub fn handle_key_event(&mut self, key: event::KeyEvent) {
-        if key.kind != KeyEventKind::Press {
-            return;
+        let deleted_at = Utc::now();
+        for todo in todos {
+            self.trash.insert(0, DeletedTodo { todo, deleted_at });
         }
+        self.trash.truncate(self.config.trash_limit);
+        let _ = self.save_trash();
+    }
 
-        match self.mode {
-            AppMode::Normal => match key.code {
-                KeyCode::Char('q') => self.should_quit = true,
-                KeyCode::Char('i') => self.mode = AppMode::Insert,
-                KeyCode::Char('?') => self.mode = AppMode::Help,
-                KeyCode::Char(' ') => self.toggle_current_todo(),
-                KeyCode::Char('d') => self.delete_current_todo(),
-                KeyCode::Up | KeyCode::Char('k') => self.previous_item(),
-                KeyCode::Down | KeyCode::Char('j') => self.next_item(),
-                _ => {}
-            },
-            AppMode::Insert => match key.code {
-                KeyCode::Esc => {
-                    self.mode = AppMode::Normal;
-                    self.input.reset();
+    pub fn open_trash(&mut self) {
+        self.trash_state
+            .select(if self.trash.is_empty() { None } else { Some(0) });
+        self.mode = AppMode::Trash;
+    }
+
+    /// Moves the selected deleted todo back into the list under a fresh id.
+    pub fn restore_deleted_todo(&mut self) {
+        if let Some(selected) = self.trash_state.selected()
+            && selected < self.trash.len()
+        {
+            let mut todo = self.trash.remove(selected).todo;
+            todo.id = self.next_id;
+            self.next_id += 1;
+            let current = self.selected_id();
+            self.todos.push(todo);
+            self.sink_completed();
+            self.reselect(current);
+
+            if self.trash.is_empty() {
+                self.trash_state.select(None);
+            } else if selected >= self.trash.len() {
+                self.trash_state.select(Some(self.trash.len() - 1));
+            }
+
+            let _ = self.save_trash();
+            let _ = self.save_todos();
+        }
+    }
+
+    /// Moves todos completed more than `auto_archive_days` ago into the
+    /// archive. Returns how many were moved.
+    fn auto_archive(&mut self, now: DateTime<Utc>) -> usize {
+        let days = self.config.auto_archive_days;
+        if days == 0 {
+            return 0;
+        }
+
+        let selected = self.selected_id();
+        let cutoff = now - chrono::Duration::days(days.into());
+        let (archived, kept): (Vec<TodoItem>, Vec<TodoItem>) = self
+            .todos
+            .iter()
+            .cloned()
+            .partition(|t| t.is_completed() && t.completed_at.is_some_and(|at| at < cutoff));
+
+        let count = archived.len();
+        if count == 0 {
+            return 0;
+        }
+        // The todos file only loses them once the archive has them
+        let archive_len = self.archive.len();
+        self.archive.extend(archived);
+        if let Err(err) = self.save_archive() {
+            logger::debug!("could not save the archive: {}", err);
+            self.archive.truncate(archive_len);
+            self.message = Some(format!("Couldn't archive old todos: {}", err));
+            return 0;
+        }
+        self.todos = kept;
+        self.reselect(selected);
+        let _ = self.save_todos();
+        count
+    }
+
+    pub fn open_archive(&mut self) {
+        self.archive_state.select(if self.archive.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+        self.mode = AppMode::Archive;
+    }
+
+    /// Moves the selected archived todo back into the list under a fresh id.
+    pub fn restore_archived_todo(&mut self) {
+        if let Some(selected) = self.archive_state.selected()
+            && selected < self.archive.len()
+        {
+            let mut todo = self.archive[selected].clone();
+            todo.id = self.next_id;
+            self.todos.push(todo);
+            // The archive only loses it once the todos file has it
+            if let Err(err) = self.save_todos() {
+                self.todos.pop();
+                self.message = Some(format!("Couldn't restore the todo: {}", err));
+                return;
+            }
+            self.next_id += 1;
+            self.archive.remove(selected);
+
+            if self.archive.is_empty() {
+                self.archive_state.select(None);
+            } else if selected >= self.archive.len() {
+                self.archive_state.select(Some(self.archive.len() - 1));
+            }
+
+            if let Err(err) = self.save_archive() {
+                logger::debug!("could not save the archive: {}", err);
+                self.message = Some(format!(
+                    "Restored, but couldn't update the archive: {}",
+                    err
+                ));
+            }
+        }
+    }
+
+    pub fn add_todo(&mut self, text: String) {
+        let _ = self.add_todo_from(&text, Source::Tui);
+    }
+
+    /// Adds a todo from quick-capture input and returns its id, or `None`
+    /// when the input is blank.
+    pub fn add_todo_from(&mut self, text: &str, source: Source) -> Result<Option<usize>> {
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        let id = self.next_id;
+        let mut todo = TodoItem::from_input(id, text);
+        if todo.priority.is_none() {
+            todo.priority = self.config.default_priority;
+        }
+        todo.parent = self.new_parent;
+        todo.source = Some(source);
+        self.todos.push(todo);
+        self.next_id += 1;
+        self.sink_completed();
+
+        // Select the new item if the current filter shows it
+        self.select_by_id(id);
+
+        self.save_todos()?;
+        Ok(Some(id))
+    }
+
+    /// Indices into `todos` of the items shown under the current filter and
+    /// search query.
+    pub fn visible_indices(&self) -> Vec<usize> {
+        self.visible_rows().into_iter().map(|(i, _)| i).collect()
+    }
+
+    /// The shown todos as (index, depth) pairs, with subtasks right after
+    /// their parent and left out while it is folded. Subtasks whose parent
+    /// isn't shown are listed at the top level.
+    pub fn visible_rows(&self) -> Vec<(usize, usize)> {
+        let mut shown: Vec<usize> = self
+            .matching_indices()
+            .into_iter()
+            .filter(|&i| !self.is_collapsed(&self.todos[i]))
+            .collect();
+        if self.overdue_view.is_some() {
+            shown.sort_by_key(|&i| (self.todos[i].due, self.todos[i].due_time));
+        }
+        let shown_ids: HashSet<usize> = shown.iter().map(|&i| self.todos[i].id).collect();
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        for &i in &shown {
+            if let Some(parent) = self.todos[i].parent.filter(|p| shown_ids.contains(p)) {
+                children.entry(parent).or_default().push(i);
+            }
+        }
+
+        let mut rows = Vec::with_capacity(shown.len());
+        let mut visited = HashSet::new();
+        let is_root = |i: usize| {
+            self.todos[i]
+                .parent
+                .is_none_or(|parent| !shown_ids.contains(&parent))
+        };
+        // Roots first, then anything only reachable through a parent cycle
+        for &i in shown.iter().filter(|&&i| is_root(i)).chain(&shown) {
+            let mut stack = vec![(i, 0)];
+            while let Some((i, depth)) = stack.pop() {
+                if !visited.insert(i) {
+                    continue;
                 }
-                KeyCode::Enter => {
-                    let input_text = self.input.value().to_string();
-                    self.add_todo(input_text);
-                    self.input.reset();
-                    self.mode = AppMode::Normal;
+                rows.push((i, depth));
+                if !self.todos[i].folded
+                    && let Some(kids) = children.get(&self.todos[i].id)
+                {
+                    stack.extend(kids.iter().rev().map(|&kid| (kid, depth + 1)));
                 }
-                _ => {
-                    self.input.handle_event(&Event::Key(key));
+            }
+        }
+        rows
+    }
+
+    /// Ids of todos that have subtasks.
+    pub fn parent_ids(&self) -> HashSet<usize> {
+        self.todos.iter().filter_map(|t| t.parent).collect()
+    }
+
+    /// Indices of all subtasks below the todo with `id`, however deep.
+    fn descendants(&self, id: usize) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut seen = HashSet::from([id]);
+        let mut pending = vec![id];
+        while let Some(parent) = pending.pop() {
+            for (i, todo) in self.todos.iter().enumerate() {
+                if todo.parent == Some(parent) && seen.insert(todo.id) {
+                    found.push(i);
+                    pending.push(todo.id);
                 }
-            },
-            AppMode::Help => match key.code {
-                KeyCode::Esc | KeyCode::Char('?') => self.mode = AppMode::Normal,
-                _ => {}
-            },
+            }
         }
+        found
     }
 
-    pub fn completed_count(&self) -> usize {
-        self.todos.iter().filter(|t| t.is_completed()).count()
+    /// Folds or unfolds the selected todo's subtasks.
+    pub fn toggle_current_fold(&mut self) {
+        let Some(index) = self.selected_index() else {
+            return;
+        };
+        let id = self.todos[index].id;
+        if !self.parent_ids().contains(&id) {
+            self.message = Some("No subtasks to fold".to_string());
+            return;
+        }
+        self.todos[index].folded = !self.todos[index].folded;
+        self.reselect(Some(id));
+        let _ = self.save_todos();
     }
 
-    pub fn total_count(&self) -> usize {
-        self.todos.len()
+    /// Number of completed todos folded into the summary line.
+    pub fn collapsed_count(&self) -> usize {
+        self.matching_indices()
+            .into_iter()
+            .filter(|&i| self.is_collapsed(&self.todos[i]))
+            .count()
+    }
+
+    /// Completed todos are folded away unless the filter asks for them.
+    fn is_collapsed(&self, todo: &TodoItem) -> bool {
+        self.collapse_completed && todo.is_completed() && self.filter != Filter::Completed
+    }
+
+    /// The current search input, parsed.
+    pub fn search_query(&self) -> SearchQuery {
+        SearchQuery::parse(self.search.value(), self.config.tag_combinator)
+    }
+
+    fn matching_indices(&self) -> Vec<usize> {
+        let query = self.search_query();
+        let today = Local::now().date_naive();
+        self.todos
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| self.filter.matches(t))
+            .filter(|(_, t)| self.due_window.matches(t, today))
+            .filter(|(_, t)| query.matches(t))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// The todos shown under the current filter and search, in list order.
+    pub fn visible_todos(&self) -> Vec<&TodoItem> {
+        self.visible_indices()
+            .into_iter()
+            .map(|i| &self.todos[i])
+            .collect()
+    }
+
+    /// Writes the todos currently shown to `file`, in the format its
+    /// extension names.
+    fn export_visible(&mut self, file: &str) {
+        let todos = self.visible_todos();
+        let exported = ExportFormat::for_path(Path::new(file)).and_then(|format| {
+            export::export_file(
+                format,
+                self.list_name(),
+                &todos,
+                &self.config.date_format,
+                file,
+            )
+        });
+        self.message = Some(match exported {
+            Ok(()) => format!("Exported {} todos to {}", todos.len(), file),
+            Err(err) => format!("Could not export: {}", err),
+        });
+    }
+
+    /// Copies the todos shown to the clipboard as a Markdown task list.
+    fn yank_visible(&mut self) {
+        let todos = self.visible_todos();
+        let copied = export::render(
+            ExportFormat::Markdown,
+            self.list_name(),
+            &todos,
+            &self.config.date_format,
+        )
+        .and_then(|markdown| hooks::copy_to_clipboard(&markdown));
+        self.message = Some(match copied {
+            Ok(()) => format!("Copied {} todos to the clipboard", todos.len()),
+            Err(err) => format!("Could not copy: {}", err),
+        });
+    }
+
+    /// Index into `todos` of the selected item.
+    fn selected_index(&self) -> Option<usize> {
+        let selected = self.list_state.selected()?;
+        self.visible_indices().get(selected).copied()
+    }
+
+    /// Path of the todos file, shown in the About popup.
+    pub fn data_file(&self) -> &str {
+        &self.data_file
+    }
+
+    fn selected_id(&self) -> Option<usize> {
+        self.selected_todo().map(|todo| todo.id)
+    }
+
+    /// Selects the todo with `id` if the current filter and search show it.
+    /// Returns whether it was found, leaving the selection alone otherwise.
+    fn select_by_id(&mut self, id: usize) -> bool {
+        let position = self
+            .visible_indices()
+            .into_iter()
+            .position(|i| self.todos[i].id == id);
+        if position.is_some() {
+            self.list_state.select(position);
+        }
+        position.is_some()
+    }
+
+    /// Keeps the todo with `id` selected if it is still shown, falling back
+    /// to the first visible todo.
+    fn reselect(&mut self, id: Option<usize>) {
+        if !id.is_some_and(|id| self.select_by_id(id)) {
+            self.select_first_visible();
+        }
+    }
+
+    pub fn selected_todo(&self) -> Option<&TodoItem> {
+        self.selected_index().map(|i| &self.todos[i])
+    }
+
+    /// Opens the first link in the selected todo's text in the browser.
+    pub fn open_current_url(&mut self) {
+        let Some(todo) = self.selected_todo() else {
+            return;
+        };
+        match todo.first_url() {
+            Some(url) => {
+                hooks::open_url(url);
+                self.message = Some(format!("Opening {}", url));
+            }
+            None => self.message = Some("No link in this todo".to_string()),
+        }
+    }
+
+    pub fn open_detail(&mut self) {
+        if self.selected_index().is_some() {
+            self.mode = AppMode::Detail;
+        }
+    }
+
+    pub fn edit_notes(&mut self) {
+        if let Some(todo) = self.selected_todo() {
+            self.notes_editor = TextArea::new(&todo.notes);
+            self.mode = AppMode::EditNotes;
+        }
+    }
+
+    pub fn save_notes(&mut self) {
+        if let Some(index) = self.selected_index() {
+            self.todos[index].notes = self.notes_editor.text().trim_end().to_string();
+            let _ = self.save_todos();
+        }
+        self.mode = AppMode::Detail;
+    }
+
+    /// Appends todos from elsewhere under fresh ids and saves the list.
+    pub fn append_todos(&mut self, todos: Vec<TodoItem>) -> Result<usize> {
+        let count = todos.len();
+        for mut todo in todos {
+            todo.id = self.next_id;
+            self.next_id += 1;
+            self.todos.push(todo);
+        }
+        self.save_todos()?;
+        Ok(count)
+    }
+
+    /// Appends the todos of another list under fresh ids, keeping subtasks
+    /// under their parents. With `dedupe`, todos whose text matches one
+    /// already in the list (ignoring case) are skipped. Returns how many
+    /// were merged and how many skipped.
+    pub fn merge_todos(&mut self, todos: Vec<TodoItem>, dedupe: bool) -> Result<(usize, usize)> {
+        let key = |todo: &TodoItem| todo.text.trim().to_lowercase();
+        let mut seen: HashSet<String> = self.todos.iter().map(key).collect();
+        let mut new_ids = HashMap::new();
+        let mut merged = Vec::new();
+        let mut skipped = 0;
+        for mut todo in todos {
+            if dedupe && !seen.insert(key(&todo)) {
+                skipped += 1;
+                continue;
+            }
+            new_ids.insert(todo.id, self.next_id);
+            todo.id = self.next_id;
+            self.next_id += 1;
+            merged.push(todo);
+        }
+        for todo in &mut merged {
+            todo.parent = todo.parent.and_then(|parent| new_ids.get(&parent).copied());
+        }
+
+        let count = merged.len();
+        self.todos.extend(merged);
+        self.sink_completed();
+        self.save_todos()?;
+        Ok((count, skipped))
+    }
+
+    /// Groups of two or more todos whose text is the same once case and
+    /// spacing are ignored.
+    pub fn duplicate_groups(&self) -> Vec<Vec<usize>> {
+        let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+        for todo in &self.todos {
+            let key = todo
+                .text
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase();
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, ids)) => ids.push(todo.id),
+                None => groups.push((key, vec![todo.id])),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(_, ids)| ids)
+            .filter(|ids| ids.len() > 1)
+            .collect()
+    }
+
+    /// Shows the duplicate report, if there is anything to report.
+    pub fn open_duplicates(&mut self) {
+        self.duplicates = self.duplicate_groups();
+        if self.duplicates.is_empty() {
+            self.message = Some("No duplicates found".to_string());
+            return;
+        }
+        self.duplicates_state.select(Some(0));
+        self.mode = AppMode::Duplicates;
+    }
+
+    /// Resolves the highlighted duplicate group, keeping its first todo.
+    /// With `merge`, the keeper also takes the others' tags, notes, highest
+    /// priority and earliest due date. The others go to the recycle bin so
+    /// they can still be restored.
+    pub fn resolve_selected_duplicates(&mut self, merge: bool) {
+        let Some(ids) = self
+            .duplicates_state
+            .selected()
+            .and_then(|selected| self.duplicates.get(selected))
+            .cloned()
+        else {
+            return;
+        };
+        let (keeper, extras) = (ids[0], &ids[1..]);
+        let (removed, kept): (Vec<TodoItem>, Vec<TodoItem>) = std::mem::take(&mut self.todos)
+            .into_iter()
+            .partition(|t| extras.contains(&t.id));
+        self.todos = kept;
+        for todo in &mut self.todos {
+            if todo.parent.is_some_and(|parent| extras.contains(&parent)) {
+                // The keeper can't become its own parent, so it moves to the top
+                todo.parent = (todo.id != keeper).then_some(keeper);
+            }
+        }
+
+        if merge && let Some(target) = self.todos.iter_mut().find(|t| t.id == keeper) {
+            for other in &removed {
+                for tag in &other.tags {
+                    if !target.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                        target.tags.push(tag.clone());
+                    }
+                }
+                if !other.notes.trim().is_empty() {
+                    if !target.notes.is_empty() {
+                        target.notes.push_str("\n\n");
+                    }
+                    target.notes.push_str(&other.notes);
+                }
+                target.priority = target.priority.max(other.priority);
+                if target.context.is_none() {
+                    target.context.clone_from(&other.context);
+                }
+                target.due = match (target.due, other.due) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+            }
+        }
+
+        let count = removed.len();
+        self.move_to_trash(removed);
+        self.clamp_selection();
+        let _ = self.save_todos();
+
+        self.duplicates = self.duplicate_groups();
+        let verb = if merge { "Merged" } else { "Deleted" };
+        self.message = Some(format!("{} {} duplicates", verb, count));
+        if self.duplicates.is_empty() {
+            self.mode = AppMode::Normal;
+        } else if let Some(selected) = self.duplicates_state.selected()
+            && selected >= self.duplicates.len()
+        {
+            self.duplicates_state
+                .select(Some(self.duplicates.len() - 1));
+        }
+    }
+
+    /// Moves the selected todo on to its next status. Finishing it goes
+    /// through the same path as toggling, and so does reopening.
+    pub fn cycle_current_status(&mut self) {
+        let Some(index) = self.selected_index() else {
+            return;
+        };
+        if self.todos[index].status == Status::Todo {
+            self.todos[index].status = Status::InProgress;
+            let _ = self.save_todos();
+        } else {
+            self.toggle_current_todo();
+        }
+    }
+
+    /// Toggles the selected todo. Un-completing a recurring todo asks for
+    /// confirmation first; confirming only reverts it, it never creates
+    /// another instance.
+    pub fn toggle_current_todo(&mut self) {
+        if let Some(index) = self.selected_index() {
+            let todo = &self.todos[index];
+            if todo.is_recurring() && todo.is_completed() {
+                let id = todo.id;
+                self.request_action(PendingAction::UncompleteRecurring { id });
+            } else {
+                self.toggle_todo(index);
+            }
+        }
+    }
+
+    fn toggle_todo(&mut self, index: usize) {
+        if index < self.todos.len() {
+            self.todos[index].toggle_completion();
+            let id = self.todos[index].id;
+            if self.todos[index].is_completed() {
+                if self.config.animate_completion && self.strikethrough() {
+                    self.completion_animations.insert(id, Instant::now());
+                }
+                self.run_complete_hook(index);
+                if self.config.ask_completion_note {
+                    self.open_prompt(Prompt::CompletionNote(id));
+                }
+                if self.todos[index].folded && self.config.complete_hidden_subtasks {
+                    for child in self.descendants(self.todos[index].id) {
+                        if !self.todos[child].is_completed() {
+                            self.todos[child].toggle_completion();
+                            self.run_complete_hook(child);
+                        }
+                    }
+                }
+            }
+            // The selection stays in place, landing on the next todo
+            self.sink_completed();
+
+            // The filter may now hide the toggled item
+            self.clamp_selection();
+
+            let _ = self.save_todos();
+        }
+    }
+
+    pub fn cycle_filter(&mut self) {
+        let selected = self.selected_id();
+        self.filter = self.filter.next();
+        self.view_mut().filter = Some(self.filter);
+        let _ = self.save_state();
+        self.reselect(selected);
+    }
+
+    /// Whether completed todos are drawn struck through.
+    pub fn strikethrough(&self) -> bool {
+        self.state
+            .strikethrough
+            .unwrap_or(self.config.strikethrough)
+    }
+
+    /// How far the strikethrough has swept across the todo with `id` at
+    /// `now`, from 0 to 1, while its completion is being animated.
+    pub fn completion_sweep(&self, id: usize, now: Instant) -> Option<f64> {
+        let started = self.completion_animations.get(&id)?;
+        let progress =
+            now.duration_since(*started).as_secs_f64() / COMPLETION_ANIMATION.as_secs_f64();
+        (progress < 1.0).then_some(progress)
+    }
+
+    /// Whether something on screen is animating and wants frequent redraws.
+    pub fn is_animating(&self, now: Instant) -> bool {
+        self.completion_animations
+            .values()
+            .any(|started| now.duration_since(*started) < COMPLETION_ANIMATION)
+    }
+
+    pub fn toggle_strikethrough(&mut self) {
+        self.state.strikethrough = Some(!self.strikethrough());
+        let _ = self.save_state();
+    }
+
+    pub fn toggle_collapse_completed(&mut self) {
+        let selected = self.selected_id();
+        self.collapse_completed = !self.collapse_completed;
+        self.reselect(selected);
+    }
+
+    pub fn cycle_due_window(&mut self) {
+        let selected = self.selected_id();
+        self.due_window = self.due_window.next();
+        self.reselect(selected);
+    }
+
+    /// Shows only pending overdue todos, most overdue first, or goes back to
+    /// the view from before.
+    pub fn toggle_overdue_view(&mut self) {
+        let selected = self.selected_id();
+        match self.overdue_view.take() {
+            Some((filter, due_window)) => {
+                self.filter = filter;
+                self.due_window = due_window;
+            }
+            None => {
+                self.overdue_view = Some((self.filter, self.due_window));
+                self.filter = Filter::Active;
+                self.due_window = DueWindow::Overdue;
+            }
+        }
+        self.reselect(selected);
+    }
+
+    /// Keeps the selection within the visible todos after the list shrank:
+    /// nothing is selected in an empty list, and a selection past the end
+    /// moves to the last todo.
+    fn clamp_selection(&mut self) {
+        let visible = self.visible_indices().len();
+        if visible == 0 {
+            self.list_state.select(None);
+        } else if let Some(selected) = self.list_state.selected()
+            && selected >= visible
+        {
+            self.list_state.select(Some(visible - 1));
+        }
+    }
+
+    fn select_first_visible(&mut self) {
+        let visible = self.visible_indices().len();
+        self.list_state
+            .select(if visible == 0 { None } else { Some(0) });
+    }
+
+    pub fn start_search(&mut self) {
+        self.history_index = None;
+        self.mode = AppMode::Search;
+    }
+
+    /// Keeps the current query applied and records it in the search history.
+    pub fn apply_search(&mut self) {
+        let query = self.search.value().trim().to_string();
+        let history = &mut self.state.search_history;
+        if !query.is_empty() && history.last() != Some(&query) {
+            history.push(query);
+            if history.len() > SEARCH_HISTORY_LIMIT {
+                history.remove(0);
+            }
+            let _ = self.save_state();
+        }
+        self.history_index = None;
+        self.mode = AppMode::Normal;
+    }
+
+    pub fn cancel_search(&mut self) {
+        let selected = self.selected_id();
+        self.search.reset();
+        self.history_index = None;
+        self.mode = AppMode::Normal;
+        self.reselect(selected);
+    }
+
+    /// Steps through the search history, older with `Up` and newer with
+    /// `Down`. Stepping past the newest entry clears the query.
+    pub fn browse_search_history(&mut self, older: bool) {
+        let history = &self.state.search_history;
+        if history.is_empty() {
+            return;
+        }
+
+        self.history_index = match (self.history_index, older) {
+            (None, true) => Some(history.len() - 1),
+            (None, false) => None,
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) if i + 1 < history.len() => Some(i + 1),
+            (Some(_), false) => None,
+        };
+
+        let query = self
+            .history_index
+            .map(|i| history[i].clone())
+            .unwrap_or_default();
+        self.search = Input::new(query);
+        self.select_first_visible();
+    }
+
+    pub fn sort_mode(&self) -> SortMode {
+        self.view().sort_mode
+    }
+
+    pub fn sort_direction(&self) -> SortDirection {
+        self.view().sort_direction
+    }
+
+    pub fn cycle_sort_mode(&mut self) {
+        let view = self.view_mut();
+        view.sort_mode = view.sort_mode.next();
+        self.apply_sort();
+    }
+
+    pub fn toggle_sort_direction(&mut self) {
+        let view = self.view_mut();
+        view.sort_direction = view.sort_direction.reversed();
+        self.apply_sort();
+    }
+
+    /// Saved preferences of the current list.
+    fn view(&self) -> ListView {
+        self.state
+            .lists
+            .get(&self.list_name)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn view_mut(&mut self) -> &mut ListView {
+        self.state.lists.entry(self.list_name.clone()).or_default()
+    }
+
+    /// Re-sorts after the sort mode or direction changed and saves both the
+    /// new order and the preference.
+    fn apply_sort(&mut self) {
+        self.sort_todos();
+        let _ = self.save_todos();
+        let _ = self.save_state();
+    }
+
+    /// Whether the list is sorted by anything but creation order.
+    fn is_sorted(&self) -> bool {
+        self.sort_mode() != SortMode::Created || self.sort_direction() != SortDirection::Ascending
+    }
+
+    /// Reorders the todos by the current sort mode and direction, keeping
+    /// the selected todo selected. The direction only applies within each
+    /// group: completed todos stay last with `completed_to_bottom`, and
+    /// todos without a value stay where [`SortMode::unset_position`] puts
+    /// them.
+    fn sort_todos(&mut self) {
+        let selected = self.selected_id();
+
+        let mode = self.sort_mode();
+        let direction = self.sort_direction();
+        let config = &self.config;
+        let group = |todo: &TodoItem| {
+            let placement = match mode.unset_position(todo, config) {
+                Some(UnsetPosition::First) => 0,
+                None => 1,
+                Some(UnsetPosition::Last) => 2,
+            };
+            (config.completed_to_bottom && todo.is_completed(), placement)
+        };
+        self.todos.sort_by(|a, b| {
+            let ordering = mode.compare(a, b);
+            let ordering = match direction {
+                SortDirection::Ascending => ordering,
+                SortDirection::Descending => ordering.reverse(),
+            };
+            group(a).cmp(&group(b)).then(ordering)
+        });
+
+        if let Some(id) = selected {
+            self.select_by_id(id);
+        }
+    }
+
+    /// Moves completed todos after the active ones when
+    /// `completed_to_bottom` is set, keeping the order within each group.
+    fn sink_completed(&mut self) {
+        if self.config.completed_to_bottom {
+            self.todos.sort_by_key(|t| t.is_completed());
+        }
+    }
+
+    fn save_state(&self) -> Result<()> {
+        self.state.save(&self.state_file)
+    }
+
+    fn run_complete_hook(&self, index: usize) {
+        if let Some(command) = &self.config.on_complete_cmd {
+            hooks::on_complete(command, &self.todos[index]);
+        }
+    }
+
+    /// Marks the todo with the given id as completed. Todos that are already
+    /// completed are left as they are.
+    pub fn complete_todo_by_id(&mut self, id: usize) -> Result<&TodoItem> {
+        let index = self
+            .todos
+            .iter()
+            .position(|t| t.id == id)
+            .ok_or_else(|| color_eyre::eyre::eyre!("No todo with id {}", id))?;
+
+        if !self.todos[index].is_completed() {
+            self.todos[index].toggle_completion();
+            self.run_complete_hook(index);
+            self.save_todos()?;
+        }
+
+        // Saving may have re-sorted the list
+        let index = self.todos.iter().position(|t| t.id == id).unwrap_or(index);
+        Ok(&self.todos[index])
+    }
+
+    /// Id of the one pending todo whose text contains `query`, ignoring
+    /// case. When several match, the error lists them so an id can be used.
+    pub fn find_pending_by_text(&self, query: &str) -> Result<usize> {
+        let needle = query.to_lowercase();
+        let matches: Vec<&TodoItem> = self
+            .todos
+            .iter()
+            .filter(|t| !t.is_completed() && t.text.to_lowercase().contains(&needle))
+            .collect();
+
+        match matches.as_slice() {
+            [] => Err(color_eyre::eyre::eyre!(
+                "No pending todo matches '{}'",
+                query
+            )),
+            [todo] => Ok(todo.id),
+            _ => {
+                let list: Vec<String> = matches
+                    .iter()
+                    .map(|t| format!("  #{} {}", t.id, t.text))
+                    .collect();
+                Err(color_eyre::eyre::eyre!(
+                    "'{}' matches {} todos, use an id instead:\n{}",
+                    query,
+                    matches.len(),
+                    list.join("\n")
+                ))
+            }
+        }
+    }
+
+    /// Pushes the selected todo's due date forward by the configured number
+    /// of days. Overdue and undated todos are snoozed relative to today.
+    pub fn snooze_current_todo(&mut self) {
+        if let Some(index) = self.selected_index() {
+            let today = Local::now().date_naive();
+            let from = self.todos[index].due.map_or(today, |due| due.max(today));
+            self.todos[index].due =
+                from.checked_add_days(Days::new(self.config.snooze_days.into()));
+            // A due window may no longer show it
+            self.clamp_selection();
+            let _ = self.save_todos();
+        }
+    }
+
+    /// Starts tracking time on the selected todo, or pauses it if it is
+    /// already running. Only one timer runs at a time, so starting one pauses
+    /// any other.
+    pub fn toggle_current_timer(&mut self) {
+        if let Some(index) = self.selected_index() {
+            let now = Utc::now();
+            if self.todos[index].is_tracking() {
+                self.todos[index].stop_timer(now);
+            } else {
+                for todo in self.todos.iter_mut() {
+                    todo.stop_timer(now);
+                }
+                self.todos[index].start_timer(now);
+            }
+            let _ = self.save_todos();
+        }
+    }
+
+    pub fn tracking_todo(&self) -> Option<&TodoItem> {
+        self.todos.iter().find(|t| t.is_tracking())
+    }
+
+    pub fn set_current_priority(&mut self, priority: Option<Priority>) {
+        if let Some(index) = self.selected_index() {
+            self.todos[index].priority = priority;
+            let _ = self.save_todos();
+        }
+    }
+
+    pub fn open_tags(&mut self) {
+        if let Some(todo) = self.selected_todo() {
+            let has_tags = !todo.tags.is_empty();
+            self.tag_state.select(has_tags.then_some(0));
+            self.mode = AppMode::Tags;
+        }
+    }
+
+    /// Removes the highlighted tag from the selected todo.
+    pub fn remove_selected_tag(&mut self) {
+        if let (Some(index), Some(tag)) = (self.selected_index(), self.tag_state.selected()) {
+            let tags = &mut self.todos[index].tags;
+            if tag < tags.len() {
+                tags.remove(tag);
+
+                let remaining = tags.len();
+                if remaining == 0 {
+                    self.tag_state.select(None);
+                } else if tag >= remaining {
+                    self.tag_state.select(Some(remaining - 1));
+                }
+
+                let _ = self.save_todos();
+            }
+        }
+    }
+
+    pub fn open_prompt(&mut self, prompt: Prompt) {
+        let initial = match prompt {
+            Prompt::RecurUntil => {
+                // Only recurring todos have an end date
+                let Some(todo) = self.selected_todo().filter(|t| t.is_recurring()) else {
+                    return;
+                };
+                todo.recur_until
+                    .map(|d| d.format(&self.config.date_format).to_string())
+                    .unwrap_or_default()
+            }
+            Prompt::GotoId => String::new(),
+            Prompt::DueDate => {
+                let Some(todo) = self.selected_todo() else {
+                    return;
+                };
+                todo.due
+                    .map(|d| d.format(&self.config.date_format).to_string())
+                    .unwrap_or_default()
+            }
+            Prompt::DueTime => {
+                let Some(todo) = self.selected_todo() else {
+                    return;
+                };
+                todo.due_time
+                    .map(|t| t.format("%H:%M").to_string())
+                    .unwrap_or_default()
+            }
+            Prompt::NewList | Prompt::Export | Prompt::CompletionNote(_) => String::new(),
+            Prompt::RenameList => match self.lists_state.selected() {
+                Some(selected) if selected < self.lists.len() => self.lists[selected].clone(),
+                _ => return,
+            },
+        };
+        self.input = Input::new(initial);
+        self.prompt = Some(prompt);
+        self.prompt_error = None;
+        self.mode = AppMode::Prompt;
+    }
+
+    /// Reads a date typed into a prompt, in `date_format` as the prompt
+    /// was filled in, or anything [`parse_due_date`] accepts.
+    fn parse_date_input(&self, value: &str, today: NaiveDate) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(value, &self.config.date_format)
+            .ok()
+            .or_else(|| parse_due_date(value, today))
+    }
+
+    pub fn submit_prompt(&mut self) {
+        let value = self.input.value().trim().to_string();
+        let prompt = self.prompt;
+
+        // Stay in the prompt so a typo can be fixed rather than retyped
+        let today = Local::now().date_naive();
+        if matches!(prompt, Some(Prompt::DueDate | Prompt::RecurUntil))
+            && !value.is_empty()
+            && self.parse_date_input(&value, today).is_none()
+        {
+            self.prompt_error = Some(format!("Couldn't parse '{}'", value));
+            return;
+        }
+
+        self.close_prompt();
+        match (prompt, self.selected_index()) {
+            (Some(Prompt::RecurUntil), Some(index)) => {
+                if value.is_empty() {
+                    self.todos[index].recur_until = None;
+                    let _ = self.save_todos();
+                } else if let Some(date) = self.parse_date_input(&value, today) {
+                    self.todos[index].recur_until = Some(date);
+                    let _ = self.save_todos();
+                }
+            }
+            (Some(Prompt::GotoId), _) => self.goto_id(&value),
+            (Some(Prompt::Export), _) => self.export_visible(&value),
+            (Some(Prompt::CompletionNote(id)), _) if !value.is_empty() => {
+                if let Some(todo) = self.todos.iter_mut().find(|t| t.id == id) {
+                    todo.completion_note = Some(value);
+                    let _ = self.save_todos();
+                }
+            }
+            (Some(Prompt::DueDate), Some(index)) => {
+                self.todos[index].due = self.parse_date_input(&value, today);
+                if self.todos[index].due.is_none() {
+                    self.todos[index].due_time = None;
+                }
+                self.clamp_selection();
+                let _ = self.save_todos();
+            }
+            (Some(Prompt::DueTime), Some(index)) => self.set_due_time(index, &value),
+            (Some(Prompt::NewList), _) => match self.create_list(&value) {
+                Ok(()) => self.mode = AppMode::Normal,
+                Err(err) => self.message = Some(err.to_string()),
+            },
+            (Some(Prompt::RenameList), _) => {
+                if let Err(err) = self.rename_selected_list(&value) {
+                    self.message = Some(err.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Sets or clears a todo's due time. A todo without a due date becomes
+    /// due today.
+    fn set_due_time(&mut self, index: usize, value: &str) {
+        if value.is_empty() {
+            self.todos[index].due_time = None;
+        } else if let Ok(time) = NaiveTime::parse_from_str(value, "%H:%M") {
+            let todo = &mut self.todos[index];
+            todo.due_time = Some(time);
+            todo.due.get_or_insert_with(|| Local::now().date_naive());
+        } else {
+            self.message = Some(format!("'{}' is not a valid time (HH:MM)", value));
+            return;
+        }
+        self.clamp_selection();
+        let _ = self.save_todos();
+    }
+
+    /// Sends a notification for each pending todo whose due time passed
+    /// since the last check, when `notify_due` is enabled.
+    pub fn notify_due(&mut self, now: NaiveDateTime) {
+        let since = std::mem::replace(&mut self.due_checked_at, now);
+        if !self.config.notify_due {
+            return;
+        }
+        for todo in &self.todos {
+            if !todo.is_completed() && todo.due_at().is_some_and(|at| since < at && at <= now) {
+                hooks::notify_due(todo);
+            }
+        }
+    }
+
+    /// Selects the todo with the id typed into the goto prompt, widening the
+    /// filter and clearing the search when they hide it.
+    fn goto_id(&mut self, value: &str) {
+        let Ok(id) = value.trim_start_matches('#').parse::<usize>() else {
+            self.message = Some(format!("'{}' is not a valid id", value));
+            return;
+        };
+        if !self.todos.iter().any(|todo| todo.id == id) {
+            self.message = Some(format!("No todo with id {}", id));
+            return;
+        }
+
+        if !self.select_by_id(id) {
+            self.filter = Filter::All;
+            self.search.reset();
+            self.select_by_id(id);
+        }
+    }
+
+    pub fn close_prompt(&mut self) {
+        // List prompts are opened from the list picker, so go back there
+        self.mode = match self.prompt {
+            Some(Prompt::NewList | Prompt::RenameList) => AppMode::Lists,
+            _ => AppMode::Normal,
+        };
+        self.input.reset();
+        self.prompt = None;
+        self.prompt_error = None;
+    }
+
+    /// Moves the selected todo's progress by `steps` increments. Reaching
+    /// 100% completes the todo and dropping below it reopens it; steps that
+    /// stay on one side of 100% leave the todo done or open as it was.
+    pub fn adjust_current_progress(&mut self, steps: i16) {
+        let Some(index) = self.selected_index() else {
+            return;
+        };
+        let todo = &mut self.todos[index];
+        let current = todo
+            .progress
+            .unwrap_or(if todo.is_completed() { 100 } else { 0 });
+        let progress = (current as i16 + steps * PROGRESS_STEP).clamp(0, 100) as u8;
+        todo.progress = (progress > 0).then_some(progress);
+
+        let crossed = (progress == 100) != (current == 100);
+        if crossed && (progress == 100) != todo.is_completed() {
+            self.toggle_todo(index);
+        } else {
+            let _ = self.save_todos();
+        }
+    }
+
+    pub fn start_insert(&mut self, rapid: bool) {
+        self.rapid_entry = rapid;
+        self.new_parent = None;
+        self.mode = AppMode::Insert;
+    }
+
+    /// Starts adding subtasks to the selected todo, unfolding it so they
+    /// show up as they are added.
+    pub fn start_subtask_insert(&mut self) {
+        let Some(index) = self.selected_index() else {
+            return;
+        };
+        self.todos[index].folded = false;
+        self.start_insert(self.config.rapid_entry);
+        self.new_parent = Some(self.todos[index].id);
+    }
+
+    pub fn toggle_current_recurrence(&mut self) {
+        if let Some(index) = self.selected_index() {
+            self.todos[index].toggle_daily_recurrence();
+            let _ = self.save_todos();
+        }
+    }
+
+    pub fn delete_current_todo(&mut self) {
+        if let Some(index) = self.selected_index() {
+            let visible = self.visible_indices();
+            let position = self.list_state.selected().unwrap_or_default();
+            let id_at = |position: Option<usize>| {
+                position
+                    .and_then(|p| visible.get(p))
+                    .map(|&i| self.todos[i].id)
+            };
+            let (before, after) = (id_at(position.checked_sub(1)), id_at(Some(position + 1)));
+            let focus = match self.config.after_delete_focus {
+                DeleteFocus::Same => None,
+                DeleteFocus::Next => after.or(before),
+                DeleteFocus::Previous => before.or(after),
+            };
+
+            let todo = self.todos.remove(index);
+            let (id, text) = (todo.id, todo.text.clone());
+            self.move_to_trash(vec![todo]);
+            // Nothing to offer when the recycle bin is turned off
+            self.undo_delete = self
+                .trash
+                .first()
+                .filter(|d| d.todo.id == id)
+                .map(|_| UndoDelete {
+                    id,
+                    text,
+                    index,
+                    until: Instant::now() + UNDO_WINDOW,
+                });
+
+            if !focus.is_some_and(|id| self.select_by_id(id)) {
+                self.clamp_selection();
+            }
+
+            let _ = self.save_todos();
+        }
+    }
+
+    /// The undo offer for the last delete, unless it has run out at `now`.
+    pub fn pending_undo(&self, now: Instant) -> Option<&UndoDelete> {
+        self.undo_delete.as_ref().filter(|undo| now < undo.until)
+    }
+
+    /// Puts the todo deleted last back where it was, while the undo offer
+    /// is still up.
+    pub fn undo_last_delete(&mut self, now: Instant) {
+        let Some(undo) = self.pending_undo(now).cloned() else {
+            self.message = Some("Nothing to undo".to_string());
+            return;
+        };
+        self.undo_delete = None;
+        if self.trash.first().is_none_or(|d| d.todo.id != undo.id) {
+            return;
+        }
+
+        let todo = self.trash.remove(0).todo;
+        self.todos.insert(undo.index.min(self.todos.len()), todo);
+        self.sink_completed();
+        self.reselect(Some(undo.id));
+        let _ = self.save_trash();
+        let _ = self.save_todos();
+    }
+
+    pub fn request_clear_completed(&mut self) {
+        let count = self.completed_count();
+        if count > 0 {
+            self.request_action(PendingAction::ClearCompleted { count });
+        }
+    }
+
+    pub fn request_delete(&mut self) {
+        if let Some(todo) = self.selected_todo() {
+            let id = todo.id;
+            self.request_action(PendingAction::Delete { id });
+        }
+    }
+
+    /// Re-reads the list from disk, asking first if that would throw away
+    /// changes that failed to save.
+    pub fn request_reload(&mut self) {
+        if self.dirty {
+            self.request_action(PendingAction::Reload);
+        } else {
+            self.reload();
+        }
+    }
+
+    /// Applies the theme as it is in the config file now.
+    pub fn reload_theme(&mut self) {
+        match Config::load_theme() {
+            Ok(theme) => {
+                self.config.theme = theme;
+                self.message = Some("Theme reloaded".to_string());
+            }
+            Err(err) => {
+                logger::debug!("could not reload theme: {}", err);
+                self.message = Some(format!("Theme not reloaded: {}", err));
+            }
+        }
+    }
+
+    /// Replaces the todos with what is on disk, keeping the selected todo
+    /// selected if it is still there.
+    pub fn reload(&mut self) {
+        let selected = self.selected_id();
+        let today = Local::now().date_naive();
+        match Self::read_todos(&self.data_file, self.view().last_opened, today) {
+            Ok((todos, changed)) => {
+                self.next_id = todos.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+                self.todos = todos;
+                self.sink_completed();
+                self.undo_delete = None;
+                self.data_hash = file_hash(Path::new(&self.data_file));
+                self.dirty = false;
+                self.reselect(selected);
+                if changed {
+                    let _ = self.save_todos();
+                }
+                self.message = Some(format!("Reloaded {} todos", self.todos.len()));
+                // A todo waiting for confirmation may be gone from the file
+                if self
+                    .pending_action
+                    .and_then(PendingAction::todo_id)
+                    .is_some_and(|id| !self.todos.iter().any(|t| t.id == id))
+                {
+                    self.cancel_pending_action();
+                    self.message =
+                        Some("Reloaded; the todo you were asked about is gone".to_string());
+                }
+                // The other instance may have closed the list by now
+                if self.read_only && self.locking {
+                    let _ = self.lock_list();
+                }
+            }
+            Err(err) => self.message = Some(format!("Could not reload: {}", err)),
+        }
+    }
+
+    /// Reloads the list once another program has changed its file. Events
+    /// caused by our own writes are ignored since the content is unchanged.
+    pub fn check_file_changes(&mut self, now: Instant) {
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        if !watcher.poll(Path::new(&self.data_file), now) {
+            return;
+        }
+
+        let hash = file_hash(Path::new(&self.data_file));
+        if hash == self.data_hash {
+            return;
+        }
+        logger::debug!("{} changed on disk", self.data_file);
+        if self.dirty {
+            // Don't throw away changes that haven't been saved
+            self.data_hash = hash;
+            self.message = Some("The list changed on disk, press R to reload".to_string());
+        } else {
+            self.reload();
+        }
+    }
+
+    pub fn request_quit(&mut self) {
+        self.request_action(PendingAction::Quit);
+    }
+
+    /// Runs `action`, asking for confirmation first when `confirm_level`
+    /// covers it.
+    fn request_action(&mut self, action: PendingAction) {
+        if self.config.confirm_level >= action.confirm_level() {
+            self.pending_action = Some(action);
+            self.mode = AppMode::Confirm;
+        } else {
+            self.run_action(action);
+        }
+    }
+
+    fn run_action(&mut self, action: PendingAction) {
+        match action {
+            PendingAction::ClearCompleted { .. } => self.clear_completed(),
+            PendingAction::UncompleteRecurring { id } => {
+                if let Some(index) = self.todos.iter().position(|t| t.id == id) {
+                    self.toggle_todo(index);
+                }
+            }
+            PendingAction::Delete { id } => {
+                if self.select_by_id(id) {
+                    self.delete_current_todo();
+                }
+            }
+            PendingAction::Reload => self.reload(),
+            PendingAction::Quit => self.should_quit = true,
+        }
+    }
+
+    pub fn clear_completed(&mut self) {
+        let selected = self.selected_id();
+        let (completed, remaining) = std::mem::take(&mut self.todos)
+            .into_iter()
+            .partition(|t| t.is_completed());
+        self.todos = remaining;
+        self.move_to_trash(completed);
+
+        // Stay on the selected todo unless it was one of those cleared
+        if !selected.is_some_and(|id| self.select_by_id(id)) {
+            self.clamp_selection();
+        }
+
+        let _ = self.save_todos();
+    }
+
+    pub fn confirm_pending_action(&mut self) {
+        self.mode = AppMode::Normal;
+        if let Some(action) = self.pending_action.take() {
+            self.run_action(action);
+        }
+    }
+
+    pub fn cancel_pending_action(&mut self) {
+        self.pending_action = None;
+        self.mode = AppMode::Normal;
+    }
+
+    fn todo_text(&self, id: usize) -> &str {
+        self.todos
+            .iter()
+            .find(|t| t.id == id)
+            .map_or("", |t| t.text.as_str())
+    }
+
+    pub fn confirm_message(&self) -> Option<String> {
+        self.pending_action.map(|action| match action {
+            PendingAction::ClearCompleted { count } => format!(
+                "Remove {} completed todo{}? (y/n)",
+                count,
+                if count == 1 { "" } else { "s" }
+            ),
+            PendingAction::UncompleteRecurring { id } => format!(
+                "Mark recurring todo '{}' as not done? (y/n)",
+                self.todo_text(id)
+            ),
+            PendingAction::Delete { id } => {
+                format!("Delete '{}'? (y/n)", self.todo_text(id))
+            }
+            PendingAction::Reload => "Reload from disk and lose unsaved changes? (y/n)".to_string(),
+            PendingAction::Quit => "Quit oxitodo? (y/n)".to_string(),
+        })
+    }
+
+    pub fn next_item(&mut self) {
+        let visible = self.visible_indices().len();
+        if visible == 0 {
+            return;
+        }
+
+        let selected = match self.list_state.selected() {
+            Some(i) => {
+                if i >= visible - 1 {
+                    if visible > 1 {
+                        self.message = Some("Wrapped to top".to_string());
+                    }
+                    0
+                } else {
+                    i + 1
+                }
+            }
+            None => 0,
+        };
+        self.list_state.select(Some(selected));
+    }
+
+    pub fn previous_item(&mut self) {
+        let visible = self.visible_indices().len();
+        if visible == 0 {
+            return;
+        }
+
+        let selected = match self.list_state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    if visible > 1 {
+                        self.message = Some("Wrapped to bottom".to_string());
+                    }
+                    visible - 1
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        self.list_state.select(Some(selected));
+    }
+
+    /// The page the selection is on, counted from 0, and how many pages
+    /// there are, one per screenful of rows.
+    pub fn page(&self) -> (usize, usize) {
+        let size = self.list_height().max(1);
+        let visible = self.visible_indices().len();
+        let selected = self.list_state.selected().unwrap_or_default();
+        (selected / size, visible.div_ceil(size).max(1))
+    }
+
+    /// Moves to the next or previous page, selecting its first todo or,
+    /// with `page_keeps_row`, the one on the same row.
+    pub fn change_page(&mut self, forward: bool) {
+        let (page, pages) = self.page();
+        let target = if forward {
+            (page + 1).min(pages - 1)
+        } else {
+            page.saturating_sub(1)
+        };
+        if target == page {
+            return;
+        }
+
+        let size = self.list_height().max(1);
+        let row = if self.config.page_keeps_row {
+            self.list_state.selected().unwrap_or_default() % size
+        } else {
+            0
+        };
+        let last = self.visible_indices().len().saturating_sub(1);
+        self.list_state
+            .select(Some((target * size + row).min(last)));
+        if self.config.paged {
+            *self.list_state.offset_mut() = target * size;
+        }
+    }
+
+    /// Moves the selection to the next (or previous) overdue todo in view,
+    /// wrapping around at the ends.
+    pub fn jump_to_overdue(&mut self, forward: bool, today: NaiveDate) {
+        let overdue: Vec<usize> = self
+            .visible_indices()
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, i)| self.todos[i].is_overdue(today))
+            .map(|(position, _)| position)
+            .collect();
+        let (Some(&first), Some(&last)) = (overdue.first(), overdue.last()) else {
+            self.message = Some("No overdue todos".to_string());
+            return;
+        };
+
+        let target = match (self.list_state.selected(), forward) {
+            (Some(current), true) => overdue.iter().copied().find(|&p| p > current),
+            (Some(current), false) => overdue.iter().rev().copied().find(|&p| p < current),
+            (None, _) => None,
+        };
+        let target = target.unwrap_or(if forward { first } else { last });
+        self.list_state.select(Some(target));
+    }
+
+    /// Carries out a Normal mode action.
+    pub fn perform(&mut self, action: Action) {
+        match action {
+            Action::MoveUp => self.previous_item(),
+            Action::MoveDown => self.next_item(),
+            Action::NextPage => self.change_page(true),
+            Action::PreviousPage => self.change_page(false),
+            Action::NextOverdue => self.jump_to_overdue(true, Local::now().date_naive()),
+            Action::PreviousOverdue => self.jump_to_overdue(false, Local::now().date_naive()),
+            Action::GotoId => self.open_prompt(Prompt::GotoId),
+            Action::Add => self.start_insert(self.config.rapid_entry),
+            Action::AddMany => self.start_insert(true),
+            Action::AddSubtask => self.start_subtask_insert(),
+            Action::ToggleFold => self.toggle_current_fold(),
+            Action::Toggle => self.toggle_current_todo(),
+            Action::CycleStatus => self.cycle_current_status(),
+            Action::Delete => self.request_delete(),
+            Action::UndoDelete => self.undo_last_delete(Instant::now()),
+            Action::ClearCompleted => self.request_clear_completed(),
+            Action::ToggleRecurrence => self.toggle_current_recurrence(),
+            Action::SetRecurUntil => self.open_prompt(Prompt::RecurUntil),
+            Action::SetDueDate => self.open_prompt(Prompt::DueDate),
+            Action::SetDueTime => self.open_prompt(Prompt::DueTime),
+            Action::Snooze => self.snooze_current_todo(),
+            Action::ToggleTimer => self.toggle_current_timer(),
+            Action::IncreaseProgress => self.adjust_current_progress(1),
+            Action::DecreaseProgress => self.adjust_current_progress(-1),
+            Action::PriorityLow => self.set_current_priority(Some(Priority::Low)),
+            Action::PriorityMedium => self.set_current_priority(Some(Priority::Medium)),
+            Action::PriorityHigh => self.set_current_priority(Some(Priority::High)),
+            Action::ClearPriority => self.set_current_priority(None),
+            Action::OpenTags => self.open_tags(),
+            Action::ToggleCompact => self.dense = !self.dense,
+            Action::ToggleStrikethrough => self.toggle_strikethrough(),
+            Action::CycleFilter => self.cycle_filter(),
+            Action::CycleDueWindow => self.cycle_due_window(),
+            Action::CollapseCompleted => self.toggle_collapse_completed(),
+            Action::OverdueView => self.toggle_overdue_view(),
+            Action::Search => self.start_search(),
+            Action::CycleSort => self.cycle_sort_mode(),
+            Action::ReverseSort => self.toggle_sort_direction(),
+            Action::OpenDetail => self.open_detail(),
+            Action::OpenLink => self.open_current_url(),
+            Action::OpenArchive => self.open_archive(),
+            Action::OpenTrash => self.open_trash(),
+            Action::OpenLists => self.open_lists(),
+            Action::PreviousList => self.switch_to_previous_list(),
+            Action::Export => self.open_prompt(Prompt::Export),
+            Action::YankList => self.yank_visible(),
+            Action::Reload => self.request_reload(),
+            Action::ReloadTheme => self.reload_theme(),
+            Action::Help => {
+                self.help_scroll = 0;
+                self.mode = AppMode::Help;
+            }
+            Action::CheatSheet => {
+                self.help_scroll = 0;
+                self.mode = AppMode::CheatSheet;
+            }
+            Action::Quit => self.request_quit(),
+        }
+    }
+
+    pub fn handle_key_event(&mut self, key: event::KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        logger::debug!("key {:?} in {:?} mode", key.code, self.mode);
+        self.message = None;
+
+        match self.mode {
+            AppMode::Normal => {
+                if let Some(action) = self.keymap.action_for(key.code, key.modifiers) {
+                    self.perform(action);
+                }
+            }
+            AppMode::Insert => match key.code {
+                KeyCode::Esc => {
+                    self.mode = AppMode::Normal;
+                    self.input.reset();
+                }
+                KeyCode::Enter => {
+                    let input_text = self.input.value().to_string();
+                    if input_text.trim().is_empty() {
+                        // In rapid entry an empty line finishes, like Esc
+                        if self.rapid_entry || self.config.empty_enter == EmptyEnter::Exit {
+                            self.mode = AppMode::Normal;
+                        }
+                        self.input.reset();
+                    } else {
+                        if !self.rapid_entry {
+                            self.mode = AppMode::Normal;
+                        }
+                        self.add_todo(input_text);
+                        self.input.reset();
+                    }
+                }
+                _ => {
+                    self.input.handle_event(&Event::Key(key));
+                }
+            },
+            AppMode::Prompt => match key.code {
+                KeyCode::Esc => self.close_prompt(),
+                KeyCode::Enter => self.submit_prompt(),
+                _ => {
+                    self.input.handle_event(&Event::Key(key));
+                    self.prompt_error = None;
+                }
+            },
+            AppMode::Help => match key.code {
+                KeyCode::Esc | KeyCode::Char('?') => self.mode = AppMode::Normal,
+                KeyCode::Char('a') => self.mode = AppMode::About,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.help_scroll = self.help_scroll.saturating_add(1)
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.help_scroll = self.help_scroll.saturating_sub(1)
+                }
+                _ => {}
+            },
+            AppMode::CheatSheet => match key.code {
+                KeyCode::Esc | KeyCode::F(1) => self.mode = AppMode::Normal,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.help_scroll = self.help_scroll.saturating_add(1)
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.help_scroll = self.help_scroll.saturating_sub(1)
+                }
+                _ => {}
+            },
+            AppMode::Confirm => match key.code {
+                KeyCode::Char('y') => self.confirm_pending_action(),
+                _ => self.cancel_pending_action(),
+            },
+            AppMode::Search => match key.code {
+                KeyCode::Esc => self.cancel_search(),
+                KeyCode::Enter => self.apply_search(),
+                KeyCode::Up => self.browse_search_history(true),
+                KeyCode::Down => self.browse_search_history(false),
+                _ => {
+                    self.search.handle_event(&Event::Key(key));
+                    self.select_first_visible();
+                }
+            },
+            AppMode::Dashboard => self.mode = AppMode::Normal,
+            AppMode::Archive => match key.code {
+                KeyCode::Esc | KeyCode::Char('A') => self.mode = AppMode::Normal,
+                KeyCode::Up | KeyCode::Char('k') => {
+                    cycle_selection(&mut self.archive_state, self.archive.len(), false)
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    cycle_selection(&mut self.archive_state, self.archive.len(), true)
+                }
+                KeyCode::Enter | KeyCode::Char('u') => self.restore_archived_todo(),
+                _ => {}
+            },
+            AppMode::Detail => match key.code {
+                KeyCode::Esc | KeyCode::Char('v') | KeyCode::Char('q') => {
+                    self.mode = AppMode::Normal
+                }
+                KeyCode::Char('e') => self.edit_notes(),
+                _ => {}
+            },
+            AppMode::About => {
+                if key.code == KeyCode::Esc {
+                    self.mode = AppMode::Help;
+                }
+            }
+            AppMode::Trash => match key.code {
+                KeyCode::Esc | KeyCode::Char('D') => self.mode = AppMode::Normal,
+                KeyCode::Up | KeyCode::Char('k') => {
+                    cycle_selection(&mut self.trash_state, self.trash.len(), false)
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    cycle_selection(&mut self.trash_state, self.trash.len(), true)
+                }
+                KeyCode::Enter | KeyCode::Char('u') => self.restore_deleted_todo(),
+                _ => {}
+            },
+            AppMode::Lists => match key.code {
+                KeyCode::Esc | KeyCode::Char('L') => self.mode = AppMode::Normal,
+                KeyCode::Up | KeyCode::Char('k') => {
+                    cycle_selection(&mut self.lists_state, self.lists.len(), false)
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    cycle_selection(&mut self.lists_state, self.lists.len(), true)
+                }
+                KeyCode::Enter => self.switch_to_selected_list(),
+                KeyCode::Char('n') => self.open_prompt(Prompt::NewList),
+                KeyCode::Char('r') => self.open_prompt(Prompt::RenameList),
+                _ => {}
+            },
+            AppMode::Duplicates => match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => self.mode = AppMode::Normal,
+                KeyCode::Up | KeyCode::Char('k') => {
+                    cycle_selection(&mut self.duplicates_state, self.duplicates.len(), false)
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    cycle_selection(&mut self.duplicates_state, self.duplicates.len(), true)
+                }
+                KeyCode::Char('m') => self.resolve_selected_duplicates(true),
+                KeyCode::Char('d') => self.resolve_selected_duplicates(false),
+                _ => {}
+            },
+            AppMode::Tags => match key.code {
+                KeyCode::Esc | KeyCode::Char('T') => self.mode = AppMode::Normal,
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let len = self.selected_todo().map_or(0, |t| t.tags.len());
+                    cycle_selection(&mut self.tag_state, len, false)
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let len = self.selected_todo().map_or(0, |t| t.tags.len());
+                    cycle_selection(&mut self.tag_state, len, true)
+                }
+                KeyCode::Enter | KeyCode::Char('d') => self.remove_selected_tag(),
+                _ => {}
+            },
+            AppMode::EditNotes => match key.code {
+                KeyCode::Esc => self.mode = AppMode::Detail,
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.save_notes()
+                }
+                _ => self.notes_editor.handle_key(key),
+            },
+        }
+    }
+
+    pub fn day_summary(&self, today: NaiveDate) -> DaySummary {
+        let mut summary = DaySummary::default();
+        for todo in &self.todos {
+            if todo.is_completed() {
+                if todo
+                    .completed_at
+                    .is_some_and(|at| at.with_timezone(&Local).date_naive() == today)
+                {
+                    summary.completed_today += 1;
+                }
+                continue;
+            }
+
+            summary.active += 1;
+            if todo.is_overdue(today) {
+                summary.overdue += 1;
+            } else if todo.due == Some(today) {
+                summary.due_today += 1;
+            }
+        }
+        summary
+    }
+
+    /// Collects the week so far, Monday through `today`, for a review.
+    pub fn weekly_review(&self, today: NaiveDate) -> WeeklyReview<'_> {
+        let since = today - chrono::Duration::days(today.weekday().num_days_from_monday().into());
+        let this_week = |at: Option<DateTime<Utc>>| {
+            at.is_some_and(|at| at.with_timezone(&Local).date_naive() >= since)
+        };
+
+        let mut completed: Vec<&TodoItem> = self
+            .todos
+            .iter()
+            .chain(&self.archive)
+            .filter(|t| t.is_completed() && this_week(t.completed_at))
+            .collect();
+        completed.sort_by_key(|t| t.completed_at);
+        let overdue = self.todos.iter().filter(|t| t.is_overdue(today)).collect();
+        let mut added: Vec<&TodoItem> = self
+            .todos
+            .iter()
+            .chain(&self.archive)
+            .filter(|t| this_week(t.created_at))
+            .collect();
+        added.sort_by_key(|t| t.created_at);
+
+        WeeklyReview {
+            since,
+            completed,
+            overdue,
+            added,
+        }
+    }
+
+    /// Time-based work, done every tick whether or not keys were pressed.
+    pub fn tick(&mut self, now: Instant) {
+        if self.save_due.is_some_and(|due| now >= due)
+            && let Err(err) = self.write_list()
+        {
+            logger::debug!("could not save {}: {}", self.data_file, err);
+            self.message = Some(format!("Could not save: {}", err));
+        }
+        self.notify_due(Local::now().naive_local());
+        self.check_file_changes(now);
+        self.check_idle(now);
+        // The toast has gone, so the offer goes with it
+        if self.undo_delete.is_some() && self.pending_undo(now).is_none() {
+            self.undo_delete = None;
+        }
+        self.completion_animations
+            .retain(|_, started| now.duration_since(*started) < COMPLETION_ANIMATION);
+    }
+
+    /// Quits once `idle_timeout_secs` have passed without any input.
+    pub fn check_idle(&mut self, now: Instant) {
+        let timeout = self.config.idle_timeout_secs;
+        if timeout > 0 && now.duration_since(self.last_activity) >= Duration::from_secs(timeout) {
+            logger::debug!("idle for {} seconds, quitting", timeout);
+            self.should_quit = true;
+        }
+    }
+
+    /// Records the new terminal size and scrolls the list so the selected
+    /// todo stays within the visible rows.
+    pub fn handle_resize(&mut self, width: u16, height: u16) {
+        self.terminal_size = (width, height);
+
+        let rows = self.list_height();
+        if let Some(selected) = self.list_state.selected()
+            && rows > 0
+        {
+            let offset = self.list_state.offset();
+            if selected < offset {
+                *self.list_state.offset_mut() = selected;
+            } else if selected >= offset + rows {
+                *self.list_state.offset_mut() = selected + 1 - rows;
+            }
+        }
+    }
+
+    /// Number of todo rows that fit in the list area, mirroring the layout
+    /// in `ui()`.
+    pub fn list_height(&self) -> usize {
+        let (_, height) = self.terminal_size;
+        let borders = if self.dense { 0 } else { 2 };
+        let input = match self.mode {
+            AppMode::Insert | AppMode::Search | AppMode::Prompt if self.dense => 1,
+            AppMode::Insert | AppMode::Search | AppMode::Prompt => 3,
+            _ => 0,
+        };
+        // One row is always taken by the status bar
+        (height as usize).saturating_sub(1 + input + borders)
+    }
+
+    /// Whether there are todos and all of them are completed.
+    pub fn all_done(&self) -> bool {
+        !self.todos.is_empty() && self.completed_count() == self.total_count()
+    }
+
+    pub fn completed_count(&self) -> usize {
+        self.todos.iter().filter(|t| t.is_completed()).count()
+    }
+
+    pub fn in_progress_count(&self) -> usize {
+        self.todos.iter().filter(|t| t.is_in_progress()).count()
+    }
+
+    pub fn total_count(&self) -> usize {
+        self.todos.len()
+    }
+}
+
+/// Reads a due date typed by hand. Besides ISO dates this accepts `today`,
+/// `tomorrow`, `next week`, weekday names (the next such day after today)
+/// and offsets like `+3d`, `+2w` or `in 3 days`.
+pub fn parse_due_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+    if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+        return Some(date);
+    }
+    match input.as_str() {
+        "today" => return Some(today),
+        "tomorrow" | "tmr" => return today.checked_add_days(Days::new(1)),
+        "next week" => return today.checked_add_days(Days::new(7)),
+        _ => {}
+    }
+    if let Ok(weekday) = input.parse::<chrono::Weekday>() {
+        let ahead =
+            (weekday.num_days_from_monday() + 6 - today.weekday().num_days_from_monday()) % 7 + 1;
+        return today.checked_add_days(Days::new(ahead.into()));
+    }
+
+    // Offsets: "+3d", "+2w", "in 3 days", "in 1 week"
+    let offset = input
+        .strip_prefix('+')
+        .or_else(|| input.strip_prefix("in "))?
+        .trim();
+    let split = offset
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(offset.len());
+    let count: u64 = offset[..split].parse().ok()?;
+    let days = match offset[split..].trim() {
+        "d" | "day" | "days" => count,
+        "w" | "week" | "weeks" => count.checked_mul(7)?,
+        _ => return None,
+    };
+    today.checked_add_days(Days::new(days))
+}
+
+/// Hash of a file's contents, or `None` if it can't be read.
+fn file_hash(path: &Path) -> Option<u64> {
+    let content = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&content);
+    Some(hasher.finish())
+}
+
+/// Whether the characters of `pattern` appear in `text` in order, not
+/// necessarily next to each other.
+fn is_subsequence(pattern: &str, text: &str) -> bool {
+    let mut chars = text.chars();
+    pattern.chars().all(|c| chars.any(|t| t == c))
+}
+
+/// Moves the selection of a popup list one step, wrapping at either end.
+fn cycle_selection(state: &mut ListState, len: usize, forward: bool) {
+    if len == 0 {
+        return;
+    }
+
+    let selected = match state.selected() {
+        Some(i) if forward => (i + 1) % len,
+        Some(i) => (i + len - 1) % len,
+        None => 0,
+    };
+    state.select(Some(selected));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyEvent;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A temporary directory of its own for each test.
+    fn test_dir() -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "oxitodo-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn test_app(todos: Vec<TodoItem>) -> App {
+        let dir = test_dir();
+        App::from_parts(
+            todos,
+            Config::default(),
+            AppState::default(),
+            dir.join("state.json"),
+            dir.join("todos.json").to_string_lossy().to_string(),
+        )
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key_event(KeyEvent::from(code));
+    }
+
+    /// Feeds a sequence of key presses to the app, as if typed.
+    fn press_all(app: &mut App, codes: &[KeyCode]) {
+        for &code in codes {
+            press(app, code);
+        }
+    }
+
+    /// Types `text` one character at a time.
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    /// Todos numbered from 1 with the given texts.
+    fn numbered(texts: &[&str]) -> Vec<TodoItem> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| TodoItem::new(i + 1, text.to_string()))
+            .collect()
+    }
+
+    fn texts(app: &App) -> Vec<&str> {
+        app.todos.iter().map(|t| t.text.as_str()).collect()
+    }
+
+    fn selected_text(app: &App) -> Option<&str> {
+        app.selected_todo().map(|t| t.text.as_str())
+    }
+
+    #[test]
+    fn navigation_wraps_around_both_ends() {
+        let mut app = test_app(numbered(&["one", "two", "three"]));
+
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(selected_text(&app), Some("three"));
+        assert_eq!(app.message.as_deref(), Some("Wrapped to bottom"));
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(selected_text(&app), Some("one"));
+        assert_eq!(app.message.as_deref(), Some("Wrapped to top"));
+        press(&mut app, KeyCode::Down);
+        assert_eq!(selected_text(&app), Some("two"));
+        assert_eq!(app.message, None);
+    }
+
+    #[test]
+    fn insert_adds_on_enter_and_discards_on_escape() {
+        let mut app = test_app(numbered(&["one"]));
+
+        press(&mut app, KeyCode::Char('i'));
+        type_text(&mut app, "milk #shop");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(texts(&app), vec!["one", "milk"]);
+        assert_eq!(app.todos[1].tags, vec!["shop"]);
+        assert_eq!(selected_text(&app), Some("milk"));
+
+        press(&mut app, KeyCode::Char('i'));
+        type_text(&mut app, "eggs");
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(texts(&app), vec!["one", "milk"]);
+        assert_eq!(app.input.value(), "");
+    }
+
+    #[test]
+    fn deleting_the_last_todo_selects_the_new_last_one() {
+        let mut app = test_app(numbered(&["one", "two", "three"]));
+
+        press_all(&mut app, &[KeyCode::Char('k'), KeyCode::Char('d')]);
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(texts(&app), vec!["one", "two"]);
+        assert_eq!(selected_text(&app), Some("two"));
+
+        press_all(&mut app, &[KeyCode::Char('d'), KeyCode::Char('y')]);
+        press_all(&mut app, &[KeyCode::Char('d'), KeyCode::Char('y')]);
+        assert!(app.todos.is_empty());
+        assert_eq!(app.list_state.selected(), None);
+    }
+
+    fn completed_recurring(id: usize, text: &str) -> TodoItem {
+        let mut todo = TodoItem::new(id, text.to_string());
+        todo.toggle_daily_recurrence();
+        todo.toggle_completion();
+        todo
+    }
+
+    #[test]
+    fn yaml_storage_fills_missing_fields_like_json() {
+        let dir = std::env::temp_dir().join(format!("oxitodo-yaml-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let json = dir.join("todos.json");
+        let yaml = dir.join("todos.yaml");
+        fs::write(&json, r#"[{"id": 1, "text": "milk", "completed": false}]"#).unwrap();
+        fs::write(&yaml, "- id: 1\n  text: milk\n  completed: false\n").unwrap();
+
+        let from_json = App::load_todos(&json.to_string_lossy()).unwrap();
+        let from_yaml = App::load_todos(&yaml.to_string_lossy()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&from_json).unwrap(),
+            serde_json::to_value(&from_yaml).unwrap()
+        );
+
+        let mut app = test_app(from_yaml);
+        app.data_file = yaml.to_string_lossy().to_string();
+        app.todos[0].due = Some(day("2026-03-01"));
+        app.save_todos().unwrap();
+        let reloaded = App::load_todos(&app.data_file).unwrap();
+        assert_eq!(reloaded[0].due, Some(day("2026-03-01")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn clearing_completed_keeps_the_selected_todo_selected() {
+        let mut todos: Vec<TodoItem> = (1..=4)
+            .map(|id| TodoItem::new(id, format!("todo {}", id)))
+            .collect();
+        todos[0].toggle_completion();
+        todos[1].toggle_completion();
+        let mut app = test_app(todos);
+        app.list_state.select(Some(3));
+
+        app.clear_completed();
+
+        assert_eq!(app.selected_todo().map(|t| t.id), Some(4));
+    }
+
+    #[test]
+    fn text_match_must_pick_a_single_pending_todo() {
+        let mut done = TodoItem::new(3, "oat milk".to_string());
+        done.toggle_completion();
+        let app = test_app(vec![
+            TodoItem::new(1, "Buy milk".to_string()),
+            TodoItem::new(2, "milk the cow".to_string()),
+            done,
+        ]);
+
+        assert_eq!(app.find_pending_by_text("buy MILK").unwrap(), 1);
+        assert!(app.find_pending_by_text("oat").is_err());
+        let err = app.find_pending_by_text("milk").unwrap_err().to_string();
+        assert!(err.contains("#1 Buy milk") && err.contains("#2 milk the cow"));
+    }
+
+    #[test]
+    fn reloading_a_shorter_file_drops_a_prompt_about_a_vanished_todo() {
+        let mut app = test_app(numbered(&["one", "two", "three"]));
+        app.list_state.select(Some(2));
+        press(&mut app, KeyCode::Char('d'));
+        assert!(matches!(app.mode, AppMode::Confirm));
+
+        let path = PathBuf::from(&app.data_file);
+        app.write_todos(&path, &app.todos[..1]).unwrap();
+        app.reload();
+
+        assert!(app.pending_action.is_none());
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(texts(&app), ["one"]);
+
+        // A prompt about a todo that survives the reload still hits that todo
+        let mut app = test_app(numbered(&["one", "two", "three"]));
+        app.list_state.select(Some(1));
+        press(&mut app, KeyCode::Char('d'));
+        let path = PathBuf::from(&app.data_file);
+        let reordered = [app.todos[2].clone(), app.todos[1].clone()];
+        app.write_todos(&path, &reordered).unwrap();
+        app.reload();
+
+        assert_eq!(app.confirm_message().unwrap(), "Delete 'two'? (y/n)");
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(texts(&app), ["three"]);
+    }
+
+    #[test]
+    fn progress_steps_only_toggle_a_todo_when_crossing_full() {
+        let mut done = TodoItem::new(1, "done".to_string());
+        done.toggle_completion();
+        done.progress = Some(60);
+        let mut app = test_app(vec![done]);
+
+        press(&mut app, KeyCode::Char('+'));
+        assert_eq!(app.todos[0].progress, Some(70));
+        assert!(app.todos[0].is_completed());
+
+        let mut app = test_app(vec![TodoItem::new(1, "open".to_string())]);
+        app.todos[0].progress = Some(90);
+        press(&mut app, KeyCode::Char('+'));
+        assert!(app.todos[0].is_completed());
+        press(&mut app, KeyCode::Char('-'));
+        assert_eq!(app.todos[0].progress, Some(90));
+        assert!(!app.todos[0].is_completed());
+    }
+
+    #[test]
+    fn archiving_keeps_todos_when_the_archive_cannot_be_written() {
+        let mut old = TodoItem::new(1, "old".to_string());
+        old.toggle_completion();
+        old.completed_at = Some(Utc::now() - chrono::Duration::days(30));
+        let mut app = test_app(vec![old, TodoItem::new(2, "open".to_string())]);
+        app.config.auto_archive_days = 7;
+        let archive_file = app.archive_file.clone();
+        app.archive_file = archive_file.with_file_name("missing/archive.json");
+
+        assert_eq!(app.auto_archive(Utc::now()), 0);
+        assert_eq!(texts(&app), ["old", "open"]);
+        assert!(app.archive.is_empty());
+        assert!(app.message.is_some());
+
+        app.archive_file = archive_file;
+        assert_eq!(app.auto_archive(Utc::now()), 1);
+        assert_eq!(texts(&app), ["open"]);
+    }
+
+    #[test]
+    fn save_delay_holds_writes_back_until_a_tick_after_it() {
+        let mut app = test_app(Vec::new());
+        app.config.save_delay_ms = 500;
+        let start = Instant::now();
+
+        app.add_todo("first".to_string());
+        app.add_todo("second".to_string());
+        assert!(app.dirty);
+        let saved = fs::read_to_string(&app.data_file).unwrap_or_default();
+        assert!(!saved.contains("first"));
+
+        app.tick(start);
+        assert!(app.dirty);
+        app.tick(start + Duration::from_secs(1));
+        assert!(!app.dirty);
+        let saved = fs::read_to_string(&app.data_file).unwrap();
+        assert!(saved.contains("first") && saved.contains("second"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sigterm_saves_unsaved_changes_before_quitting() {
+        let mut app = test_app(numbered(&["one"]));
+        app.todos[0].text = "edited".to_string();
+        app.dirty = true;
+        let mut events = crate::events::EventHandler::new(&app.config);
+
+        signal_hook::low_level::raise(signal_hook::consts::SIGTERM).unwrap();
+        events.handle_events(&mut app).unwrap();
+
+        assert!(app.should_quit);
+        assert!(!app.dirty);
+        assert!(
+            fs::read_to_string(&app.data_file)
+                .unwrap()
+                .contains("edited")
+        );
+    }
+
+    #[test]
+    fn confirm_level_decides_which_actions_ask_first() {
+        let mut app = test_app(vec![
+            TodoItem::new(1, "one".to_string()),
+            TodoItem::new(2, "two".to_string()),
+        ]);
+
+        press(&mut app, KeyCode::Char('d'));
+        assert!(matches!(
+            app.pending_action,
+            Some(PendingAction::Delete { .. })
+        ));
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.todos.len(), 1);
+        press(&mut app, KeyCode::Char('q'));
+        assert!(app.should_quit);
+
+        let mut app = test_app(vec![TodoItem::new(1, "one".to_string())]);
+        app.config.confirm_level = ConfirmLevel::None;
+        press(&mut app, KeyCode::Char('d'));
+        assert!(app.todos.is_empty());
+
+        app.config.confirm_level = ConfirmLevel::All;
+        press(&mut app, KeyCode::Char('q'));
+        assert!(!app.should_quit);
+        assert!(matches!(app.pending_action, Some(PendingAction::Quit)));
+    }
+
+    #[test]
+    fn lists_keep_their_own_view_and_can_be_renamed() {
+        let mut app = test_app(vec![TodoItem::new(1, "home".to_string())]);
+        app.save_todos().unwrap();
+        app.cycle_sort_mode();
+
+        app.create_list("work").unwrap();
+        assert_eq!(app.list_name(), "work");
+        assert!(app.todos.is_empty());
+        assert_eq!(app.sort_mode(), SortMode::Created);
+
+        app.open_lists();
+        assert_eq!(app.lists, ["todos", "work"]);
+        assert!(app.rename_selected_list("todos").is_err());
+        assert!(app.rename_selected_list("a/b").is_err());
+        app.rename_selected_list("office").unwrap();
+        assert_eq!(app.list_name(), "office");
+        assert!(app.list_file("office").exists());
+        assert!(!app.list_file("work").exists());
+
+        app.switch_list("todos").unwrap();
+        assert_eq!(app.todos[0].text, "home");
+        assert_eq!(app.sort_mode(), SortMode::Text);
+    }
+
+    #[test]
+    fn tab_flips_between_the_two_latest_lists() {
+        let mut app = test_app(vec![TodoItem::new(1, "home".to_string())]);
+        app.save_todos().unwrap();
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.message.as_deref(), Some("No previous list"));
+
+        app.create_list("work").unwrap();
+        app.create_list("hobby").unwrap();
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.list_name(), "work");
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.list_name(), "hobby");
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.list_name(), "work");
+    }
+
+    #[test]
+    fn due_windows_split_todos_by_date() {
+        let due = |date: Option<&str>| {
+            let mut todo = TodoItem::new(1, "todo".to_string());
+            todo.due = date.map(day);
+            todo
+        };
+        // A Wednesday; the week ends on Sunday the 18th
+        let today = day("2026-10-14");
+
+        assert!(DueWindow::Overdue.matches(&due(Some("2026-10-13")), today));
+        assert!(DueWindow::Today.matches(&due(Some("2026-10-14")), today));
+        assert!(DueWindow::ThisWeek.matches(&due(Some("2026-10-18")), today));
+        assert!(!DueWindow::ThisWeek.matches(&due(Some("2026-10-19")), today));
+        assert!(!DueWindow::ThisWeek.matches(&due(Some("2026-10-13")), today));
+        assert!(DueWindow::NoDate.matches(&due(None), today));
+    }
+
+    #[test]
+    fn collapsing_completed_hides_them_from_navigation() {
+        let mut todos = numbered(&["one", "two", "three"]);
+        todos[1].toggle_completion();
+        let mut app = test_app(todos);
+        app.list_state.select(Some(2));
+
+        app.toggle_collapse_completed();
+        assert_eq!(app.visible_indices(), vec![0, 2]);
+        assert_eq!(app.collapsed_count(), 1);
+        assert_eq!(app.selected_todo().unwrap().text, "three");
+
+        app.toggle_collapse_completed();
+        assert_eq!(app.visible_indices(), vec![0, 1, 2]);
+        assert_eq!(app.collapsed_count(), 0);
+        assert_eq!(app.selected_todo().unwrap().text, "three");
+    }
+
+    #[test]
+    fn search_combines_tags_with_and_or() {
+        let tagged = |tags: &[&str]| {
+            let mut todo = TodoItem::new(1, "call bob".to_string());
+            todo.tags = tags.iter().map(|t| t.to_string()).collect();
+            todo
+        };
+        let both = tagged(&["Work", "urgent"]);
+        let work = tagged(&["work"]);
+
+        let and = SearchQuery::parse("#work #URGENT", TagCombinator::And);
+        assert!(and.matches(&both));
+        assert!(!and.matches(&work));
+        assert_eq!(and.tag_expression().unwrap(), "#work AND #urgent");
+
+        let or = SearchQuery::parse("#home OR #work bob", TagCombinator::And);
+        assert!(or.matches(&work));
+        assert!(!SearchQuery::parse("#home OR #errand", TagCombinator::And).matches(&work));
+        assert!(SearchQuery::parse("#home #work", TagCombinator::Or).matches(&work));
+
+        assert!(SearchQuery::parse("#wrk #URG", TagCombinator::And).matches(&both));
+        assert!(!SearchQuery::parse("#krow", TagCombinator::And).matches(&work));
+
+        let mut todo = TodoItem::new(2, "pros AND cons of #1".to_string());
+        let query = SearchQuery::parse("\"AND\" cons \"of #1\"", TagCombinator::Or);
+        assert_eq!(query.combinator, TagCombinator::Or);
+        assert_eq!(query.text, "and cons of #1");
+        assert!(query.tags.is_empty());
+        assert!(query.matches(&todo));
+        todo.text = "pros and cons".to_string();
+        assert!(!query.matches(&todo));
+    }
+
+    #[test]
+    fn first_url_skips_surrounding_punctuation() {
+        let todo = |text: &str| TodoItem::new(1, text.to_string());
+
+        assert_eq!(
+            todo("read (https://example.com/a).").first_url(),
+            Some("https://example.com/a")
+        );
+        assert_eq!(
+            todo("read https://example.com/a?b=1, then reply").first_url(),
+            Some("https://example.com/a?b=1")
+        );
+        assert_eq!(todo("no links here").first_url(), None);
+    }
+
+    #[test]
+    fn completed_to_bottom_sinks_todos_as_they_are_completed() {
+        let todos = (1..=3)
+            .map(|id| TodoItem::new(id, format!("todo {}", id)))
+            .collect();
+        let mut app = test_app(todos);
+        app.config.completed_to_bottom = true;
+        app.list_state.select(Some(0));
+
+        app.toggle_current_todo();
+        let ids: Vec<usize> = app.todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![2, 3, 1]);
+        assert_eq!(app.selected_todo().map(|t| t.id), Some(2));
+
+        app.add_todo("todo 4".to_string());
+        let ids: Vec<usize> = app.todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![2, 3, 4, 1]);
+    }
+
+    #[test]
+    fn snoozing_out_of_the_due_window_keeps_a_valid_selection() {
+        let today = Local::now().date_naive();
+        let mut todos = numbered(&["one", "two"]);
+        for todo in &mut todos {
+            todo.due = Some(today);
+        }
+        let mut app = test_app(todos);
+        app.due_window = DueWindow::Today;
+        app.list_state.select(Some(1));
+
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.list_state.selected(), Some(0));
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.list_state.selected(), None);
+        assert!(app.selected_todo().is_none());
+    }
+
+    #[test]
+    fn status_cycles_and_reads_the_old_completed_flag() {
+        let todos: Vec<TodoItem> = serde_json::from_str(
+            r#"[{"id": 1, "text": "old", "completed": true},
+                {"id": 2, "text": "new", "status": "in_progress"},
+                {"id": 3, "text": "bare"}]"#,
+        )
+        .unwrap();
+        let statuses: Vec<Status> = todos.iter().map(|t| t.status).collect();
+        assert_eq!(
+            statuses,
+            vec![Status::Done, Status::InProgress, Status::Todo]
+        );
+
+        let mut app = test_app(todos);
+        press_all(&mut app, &[KeyCode::Char('j'), KeyCode::Char('j')]);
+        press(&mut app, KeyCode::Char('p'));
+        assert_eq!(app.todos[2].status, Status::InProgress);
+        assert_eq!(app.in_progress_count(), 2);
+        press(&mut app, KeyCode::Char('p'));
+        assert!(app.todos[2].is_completed());
+        assert!(app.todos[2].completed_at.is_some());
+        press(&mut app, KeyCode::Char('p'));
+        assert_eq!(app.todos[2].status, Status::Todo);
+    }
+
+    #[test]
+    fn weekly_review_covers_monday_through_today() {
+        // A Wednesday
+        let today = day("2026-10-14");
+        let at = |date: &str| {
+            let midday = day(date).and_hms_opt(12, 0, 0).unwrap();
+            Some(
+                midday
+                    .and_local_timezone(Local)
+                    .unwrap()
+                    .with_timezone(&Utc),
+            )
+        };
+        let mut todos = numbered(&["last week", "monday", "late", "fresh"]);
+        for todo in &mut todos {
+            todo.created_at = at("2026-10-05");
+        }
+        todos[0].status = Status::Done;
+        todos[0].completed_at = at("2026-10-11");
+        todos[1].status = Status::Done;
+        todos[1].completed_at = at("2026-10-12");
+        todos[2].due = Some(day("2026-10-13"));
+        todos[3].created_at = at("2026-10-14");
+        let app = test_app(todos);
+
+        let review = app.weekly_review(today);
+        let ids = |todos: &[&TodoItem]| todos.iter().map(|t| t.id).collect::<Vec<_>>();
+        assert_eq!(review.since, day("2026-10-12"));
+        assert_eq!(ids(&review.completed), vec![2]);
+        assert_eq!(ids(&review.overdue), vec![3]);
+        assert_eq!(ids(&review.added), vec![4]);
+    }
+
+    #[test]
+    fn sorting_by_tag_groups_untagged_todos_last() {
+        let mut todos = numbered(&["plain", "b2", "a", "b1"]);
+        todos[1].tags = vec!["Beta".to_string()];
+        todos[2].tags = vec!["alpha".to_string(), "zeta".to_string()];
+        todos[3].tags = vec!["beta".to_string()];
+        todos.sort_by(|a, b| SortMode::ByTag.compare(a, b));
+
+        let texts: Vec<&str> = todos.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, vec!["a", "b2", "b1", "plain"]);
+    }
+
+    #[test]
+    fn todos_without_due_date_or_priority_keep_their_place_either_way() {
+        let mut todos = numbered(&["none", "late", "soon"]);
+        todos[1].due = Some(day("2026-10-20"));
+        todos[1].priority = Some(Priority::Low);
+        todos[2].due = Some(day("2026-10-18"));
+        todos[2].priority = Some(Priority::High);
+        let mut app = test_app(todos);
+
+        // Created -> Text -> Due
+        app.cycle_sort_mode();
+        app.cycle_sort_mode();
+        assert_eq!(texts(&app), vec!["soon", "late", "none"]);
+        app.toggle_sort_direction();
+        assert_eq!(texts(&app), vec!["late", "soon", "none"]);
+
+        app.config.no_due_position = UnsetPosition::First;
+        app.toggle_sort_direction();
+        assert_eq!(texts(&app), vec!["none", "soon", "late"]);
+
+        app.cycle_sort_mode();
+        assert_eq!(app.sort_mode(), SortMode::Priority);
+        assert_eq!(texts(&app), vec!["soon", "late", "none"]);
+        app.config.no_priority_position = UnsetPosition::First;
+        app.toggle_sort_direction();
+        assert_eq!(texts(&app), vec!["none", "late", "soon"]);
+
+        // Completed todos stay last whichever way the sort runs
+        app.config.completed_to_bottom = true;
+        app.todos
+            .iter_mut()
+            .find(|t| t.text == "late")
+            .unwrap()
+            .toggle_completion();
+        app.toggle_sort_direction();
+        assert_eq!(texts(&app), vec!["none", "soon", "late"]);
+        app.toggle_sort_direction();
+        assert_eq!(texts(&app), vec!["none", "soon", "late"]);
+    }
+
+    #[test]
+    fn due_date_prompt_accepts_words_and_rejects_typos() {
+        // A Wednesday
+        let today = day("2026-10-14");
+        let parse = |input: &str| parse_due_date(input, today);
+        assert_eq!(parse("2026-11-02"), Some(day("2026-11-02")));
+        assert_eq!(parse("Tomorrow"), Some(day("2026-10-15")));
+        assert_eq!(parse("fri"), Some(day("2026-10-16")));
+        assert_eq!(parse("wednesday"), Some(day("2026-10-21")));
+        assert_eq!(parse("+3d"), Some(day("2026-10-17")));
+        assert_eq!(parse("in 2 weeks"), Some(day("2026-10-28")));
+        assert_eq!(parse("nextweek"), None);
+
+        let mut app = test_app(numbered(&["one"]));
+        press(&mut app, KeyCode::Char('e'));
+        type_text(&mut app, "nextweek");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.mode, AppMode::Prompt));
+        assert_eq!(
+            app.prompt_error.as_deref(),
+            Some("Couldn't parse 'nextweek'")
+        );
+
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.prompt_error, None);
+        for _ in 1.."nextweek".len() {
+            press(&mut app, KeyCode::Backspace);
+        }
+        type_text(&mut app, "today");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(app.todos[0].due, Some(Local::now().date_naive()));
+    }
+
+    #[test]
+    fn date_format_without_a_date_falls_back_and_prompts_use_it() {
+        let mut config = Config {
+            date_format: "%Y-%m-%d %H:%M".to_string(),
+            ..Config::default()
+        };
+        config.validate();
+        assert_eq!(config.date_format, "%Y-%m-%d");
+        config.date_format = "%d/%m/%Y".to_string();
+        config.validate();
+        assert_eq!(config.date_format, "%d/%m/%Y");
+
+        let mut app = test_app(numbered(&["one"]));
+        app.config = config;
+        app.todos[0].due = Some(day("2026-11-02"));
+        press(&mut app, KeyCode::Char('e'));
+        assert_eq!(app.input.value(), "02/11/2026");
+        press_all(&mut app, &[KeyCode::Backspace, KeyCode::Char('7')]);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.todos[0].due, Some(day("2027-11-02")));
+    }
+
+    #[test]
+    fn recur_until_prompt_keeps_a_typo_open() {
+        let mut app = test_app(numbered(&["water plants"]));
+        app.todos[0].toggle_daily_recurrence();
+
+        press(&mut app, KeyCode::Char('U'));
+        type_text(&mut app, "2026-13-01");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.mode, AppMode::Prompt));
+        assert_eq!(
+            app.prompt_error.as_deref(),
+            Some("Couldn't parse '2026-13-01'")
+        );
+        assert_eq!(app.todos[0].recur_until, None);
+
+        press_all(&mut app, &[KeyCode::Backspace; 5]);
+        type_text(&mut app, "12-01");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(app.todos[0].recur_until, Some(day("2026-12-01")));
+    }
+
+    #[test]
+    fn quick_capture_priority_overrides_the_default() {
+        let mut app = test_app(Vec::new());
+        app.config.default_priority = Some(Priority::Medium);
+
+        app.add_todo("plain".to_string());
+        app.add_todo("urgent !high #work".to_string());
+        app.add_todo("bang! !nope".to_string());
+
+        assert_eq!(texts(&app), vec!["plain", "urgent", "bang! !nope"]);
+        let priorities: Vec<_> = app.todos.iter().map(|t| t.priority).collect();
+        assert_eq!(
+            priorities,
+            vec![
+                Some(Priority::Medium),
+                Some(Priority::High),
+                Some(Priority::Medium)
+            ]
+        );
+    }
+
+    #[test]
+    fn quick_capture_context_is_shown_apart_and_searchable() {
+        let mut app = test_app(Vec::new());
+        app.add_todo("ship it +Release #work".to_string());
+        app.add_todo("vote +1".to_string());
+        app.add_todo("water plants @home".to_string());
+        app.add_todo("meet @5pm".to_string());
+
+        assert_eq!(
+            texts(&app),
+            vec!["ship it", "vote +1", "water plants", "meet @5pm"]
+        );
+        assert_eq!(app.todos[0].context.as_deref(), Some("+Release"));
+        assert_eq!(app.todos[1].context, None);
+        assert_eq!(app.todos[2].context.as_deref(), Some("@home"));
+        assert_eq!(app.todos[3].context, None);
+
+        let query = SearchQuery::parse("+release", TagCombinator::And);
+        assert_eq!(query.context.as_deref(), Some("+release"));
+        assert!(query.matches(&app.todos[0]));
+        assert!(!query.matches(&app.todos[1]));
+
+        let query = SearchQuery::parse("+1", TagCombinator::And);
+        assert_eq!(query.context, None);
+        assert!(query.matches(&app.todos[1]));
+    }
+
+    #[test]
+    fn pages_hold_a_screenful_and_can_keep_the_row() {
+        let todos = (1..=12).map(|i| TodoItem::new(i, i.to_string())).collect();
+        let mut app = test_app(todos);
+        app.config.paged = true;
+        // Five rows between the borders and the status bar
+        app.handle_resize(40, 8);
+        app.list_state.select(Some(1));
+        assert_eq!(app.page(), (0, 3));
+
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.page(), (1, 3));
+        assert_eq!(app.list_state.selected(), Some(5));
+
+        app.config.page_keeps_row = true;
+        press_all(&mut app, &[KeyCode::Down, KeyCode::Down, KeyCode::Down]);
+        press(&mut app, KeyCode::Right);
+        // Row 4 of the last page is past the end of the list
+        assert_eq!(app.list_state.selected(), Some(11));
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.page(), (2, 3));
+        press(&mut app, KeyCode::Left);
+        assert_eq!(app.list_state.selected(), Some(6));
+    }
+
+    #[test]
+    fn all_done_needs_todos_and_every_one_completed() {
+        let mut app = test_app(Vec::new());
+        assert!(!app.all_done());
+
+        app.todos = numbered(&["one", "two"]);
+        app.todos[0].toggle_completion();
+        assert!(!app.all_done());
+        app.todos[1].toggle_completion();
+        assert!(app.all_done());
+    }
+
+    #[test]
+    fn after_delete_focus_picks_the_neighbour_to_select() {
+        let selected = |app: &App| app.selected_todo().map(|t| t.text.clone());
+        let mut app = test_app(numbered(&["one", "two", "three", "four"]));
+        app.config.after_delete_focus = DeleteFocus::Previous;
+        press_all(&mut app, &[KeyCode::Char('j'), KeyCode::Char('j')]);
+        app.delete_current_todo();
+        assert_eq!(selected(&app).as_deref(), Some("two"));
+
+        app.config.after_delete_focus = DeleteFocus::Next;
+        app.delete_current_todo();
+        assert_eq!(selected(&app).as_deref(), Some("four"));
+        // Nothing comes after the last todo
+        app.delete_current_todo();
+        assert_eq!(selected(&app).as_deref(), Some("one"));
+    }
+
+    #[test]
+    fn undo_restores_a_deleted_todo_only_within_the_window() {
+        let mut app = test_app(numbered(&["one", "two", "three"]));
+        press_all(&mut app, &[KeyCode::Char('j'), KeyCode::Char('d')]);
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(texts(&app), vec!["one", "three"]);
+        assert_eq!(app.pending_undo(Instant::now()).unwrap().text, "two");
+
+        press(&mut app, KeyCode::Char('z'));
+        assert_eq!(texts(&app), vec!["one", "two", "three"]);
+        assert_eq!(app.selected_todo().map(|t| t.id), Some(2));
+        assert!(app.trash.is_empty());
+
+        press_all(&mut app, &[KeyCode::Char('d'), KeyCode::Char('y')]);
+        app.undo_last_delete(Instant::now() + UNDO_WINDOW);
+        assert_eq!(texts(&app), vec!["one", "three"]);
+        assert_eq!(app.message.as_deref(), Some("Nothing to undo"));
+    }
+
+    #[test]
+    fn export_writes_only_the_todos_shown() {
+        let mut todos = numbered(&["work report", "buy milk"]);
+        todos[0].tags = vec!["work".to_string()];
+        let mut app = test_app(todos);
+        let file = Path::new(&app.data_file).with_file_name("work.html");
+        app.search = Input::new("#work".to_string());
+
+        press(&mut app, KeyCode::Char('E'));
+        type_text(&mut app, &file.to_string_lossy());
+        press(&mut app, KeyCode::Enter);
+
+        let html = fs::read_to_string(&file).unwrap();
+        assert!(html.contains("work report"));
+        assert!(!html.contains("buy milk"));
+        assert!(app.message.unwrap().starts_with("Exported 1 todos"));
+    }
+
+    #[test]
+    fn markdown_export_is_a_task_list() {
+        let mut todos = numbered(&["report", "milk"]);
+        todos[0].priority = Some(Priority::High);
+        todos[0].tags = vec!["work".to_string()];
+        todos[1].toggle_completion();
+        todos[1].due = Some(day("2026-10-20"));
+        let todos: Vec<&TodoItem> = todos.iter().collect();
+
+        let markdown = export::render(ExportFormat::Markdown, "todos", &todos, "%Y-%m-%d").unwrap();
+        assert_eq!(
+            markdown,
+            "# todos\n\n- [ ] **high** report #work\n- [x] milk (due 2026-10-20)\n"
+        );
+    }
+
+    #[test]
+    fn csv_import_maps_columns_by_header_and_skips_bad_rows() {
+        let path = test_dir().join("tasks.csv");
+        let file = path.to_string_lossy();
+        fs::write(
+            &path,
+            "Due,Task,Done,Priority,Tags\n\
+             2026-10-20,\"Report, final\",yes,High,#work home\n\
+             ,Milk,,,\n\
+             tomorrow,Bad date,no,,\n\
+             ,Bad priority,no,urgent,\n",
+        )
+        .unwrap();
+
+        let imported = crate::import::import_file(crate::import::ImportFormat::Csv, &file).unwrap();
+        assert_eq!(imported.skipped, 2);
+        let report = &imported.todos[0];
+        assert_eq!(report.text, "Report, final");
+        assert!(report.is_completed());
+        assert_eq!(report.priority, Some(Priority::High));
+        assert_eq!(report.due, Some(day("2026-10-20")));
+        assert_eq!(report.tags, vec!["work", "home"]);
+        assert!(!imported.todos[1].is_completed());
+
+        let todos: Vec<&TodoItem> = imported.todos.iter().collect();
+        export::export_file(ExportFormat::Csv, "todos", &todos, "%d.%m.%Y", &file).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "text,completed,priority,due,tags\n\
+             \"Report, final\",true,high,2026-10-20,work home\n\
+             Milk,false,,,\n"
+        );
+        let again = crate::import::import_file(crate::import::ImportFormat::Csv, &file).unwrap();
+        assert_eq!(again.todos.len(), 2);
+        assert_eq!(again.todos[0].tags, report.tags);
+    }
+
+    #[test]
+    fn todotxt_export_reads_back_with_the_same_fields() {
+        let path = test_dir().join("todo.txt");
+        let file = path.to_string_lossy();
+        fs::write(
+            &path,
+            "(A) 2026-10-01 Call mum +family @phone due:2026-10-20\n\n\
+             x 2026-10-17 2026-10-02 File taxes #money pri:B\n\
+             +only\n\
+             Vote +1\n",
+        )
+        .unwrap();
+
+        let imported =
+            crate::import::import_file(crate::import::ImportFormat::TodoTxt, &file).unwrap();
+        assert_eq!(imported.skipped, 1);
+        let call = &imported.todos[0];
+        assert_eq!(call.text, "Call mum @phone");
+        assert_eq!(call.context.as_deref(), Some("+family"));
+        assert_eq!(call.priority, Some(Priority::High));
+        assert_eq!(call.due, Some(day("2026-10-20")));
+        let taxes = &imported.todos[1];
+        assert!(taxes.is_completed());
+        assert_eq!(taxes.tags, vec!["money"]);
+        assert_eq!(taxes.priority, Some(Priority::Medium));
+        assert_eq!(imported.todos[2].text, "Vote +1");
+        assert_eq!(imported.todos[2].context, None);
+
+        let todos: Vec<&TodoItem> = imported.todos.iter().collect();
+        export::export_file(ExportFormat::TodoTxt, "todos", &todos, "%Y-%m-%d", &file).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "(A) 2026-10-01 Call mum @phone +family due:2026-10-20\n\
+             x 2026-10-17 2026-10-02 File taxes #money pri:B\n\
+             Vote +1\n"
+        );
+    }
+
+    #[test]
+    fn folded_subtasks_are_skipped_and_completed_with_their_parent() {
+        let mut app = test_app(numbered(&["trip", "other"]));
+        press(&mut app, KeyCode::Char('a'));
+        type_text(&mut app, "pack");
+        press(&mut app, KeyCode::Enter);
+        app.select_by_id(3);
+        press(&mut app, KeyCode::Char('a'));
+        type_text(&mut app, "socks");
+        press(&mut app, KeyCode::Enter);
+
+        let rows: Vec<(usize, usize)> = app
+            .visible_rows()
+            .into_iter()
+            .map(|(i, depth)| (app.todos[i].id, depth))
+            .collect();
+        assert_eq!(rows, vec![(1, 0), (3, 1), (4, 2), (2, 0)]);
+
+        app.list_state.select(Some(0));
+        press(&mut app, KeyCode::Char('Z'));
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(selected_text(&app), Some("other"));
+
+        press_all(&mut app, &[KeyCode::Char('k'), KeyCode::Char(' ')]);
+        assert!(
+            app.todos
+                .iter()
+                .filter(|t| t.id != 2)
+                .all(|t| t.is_completed())
+        );
+
+        // Deleting a parent hands its subtasks to the grandparent
+        app.todos[0].folded = false;
+        app.select_by_id(3);
+        app.delete_current_todo();
+        assert_eq!(
+            app.todos.iter().find(|t| t.id == 4).unwrap().parent,
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn second_instance_opens_the_list_read_only() {
+        let mut first = test_app(numbered(&["a"]));
+        let lock_file = ListLock::path_for(Path::new(&first.data_file));
+        // A lock left by a process that is gone doesn't count
+        fs::write(&lock_file, u32::MAX.to_string()).unwrap();
+        first.lock_list().unwrap();
+        assert!(!first.read_only);
+        assert_eq!(
+            fs::read_to_string(&lock_file).unwrap(),
+            std::process::id().to_string()
+        );
+
+        let mut second = test_app(numbered(&["a"]));
+        second.data_file = first.data_file.clone();
+        fs::write(&lock_file, "1").unwrap();
+        second.lock_list().unwrap();
+        assert!(second.read_only);
+        second.add_todo("b".to_string());
+        assert!(second.save_todos().is_err());
+
+        drop(first);
+        assert!(!lock_file.exists());
+    }
+
+    #[test]
+    fn switching_to_a_list_that_cannot_be_locked_stays_put() {
+        let mut app = test_app(numbered(&["a"]));
+        app.lock_list().unwrap();
+        let data_file = app.data_file.clone();
+        let other = app.list_file("other");
+        fs::write(&other, "[]").unwrap();
+        // A directory in the way makes taking the lock fail outright
+        let _ = fs::remove_file(ListLock::path_for(&other));
+        fs::create_dir_all(ListLock::path_for(&other)).unwrap();
+
+        assert!(app.switch_list("other").is_err());
+        assert_eq!(app.data_file, data_file);
+        assert_eq!(texts(&app), ["a"]);
+        assert!(app.lock.is_some());
+
+        // Another instance holding it opens it read-only instead
+        fs::remove_dir(ListLock::path_for(&other)).unwrap();
+        fs::write(ListLock::path_for(&other), "1").unwrap();
+        app.switch_list("other").unwrap();
+        assert!(app.read_only && app.lock.is_none());
+        assert!(!ListLock::path_for(Path::new(&data_file)).exists());
+    }
+
+    #[test]
+    fn auto_sort_keeps_the_order_and_selection_after_changes() {
+        let mut app = test_app(numbered(&["cherry", "banana"]));
+        app.config.auto_sort = true;
+        app.cycle_sort_mode();
+        assert_eq!(app.sort_mode(), SortMode::Text);
+
+        // The new todo stays selected as it moves into place
+        app.add_todo("apple".to_string());
+        assert_eq!(texts(&app), vec!["apple", "banana", "cherry"]);
+        assert_eq!(selected_text(&app), Some("apple"));
+
+        // Without a sort mode new todos stay at the end
+        app.cycle_sort_mode();
+        while app.sort_mode() != SortMode::Created {
+            app.cycle_sort_mode();
+        }
+        app.add_todo("aardvark".to_string());
+        assert_eq!(texts(&app).last(), Some(&"aardvark"));
+    }
+
+    #[test]
+    fn every_bound_key_dispatches_its_own_action() {
+        let keymap = Keymap::default();
+        for binding in keymap.bindings() {
+            for &key in &binding.keys {
+                let action = keymap.action_for(key.code, key.modifiers);
+                assert_eq!(action, Some(binding.action), "{:?}", key);
+            }
+        }
+        // Capitals arrive with Shift held, control keys don't fall back
+        let shift = KeyModifiers::SHIFT;
+        assert_eq!(
+            keymap.action_for(KeyCode::Char('I'), shift),
+            Some(Action::AddMany)
+        );
+        let ctrl = KeyModifiers::CONTROL;
+        assert_eq!(
+            keymap.action_for(KeyCode::Char('t'), ctrl),
+            Some(Action::ReloadTheme)
+        );
+        assert_eq!(keymap.action_for(KeyCode::Char('k'), ctrl), None);
+
+        // Hints name the first key only
+        assert_eq!(keymap.key_label(Action::Add).as_deref(), Some("i"));
+        assert_eq!(keymap.key_label(Action::Toggle).as_deref(), Some("Space"));
+
+        let mut app = test_app(numbered(&["a"]));
+        press(&mut app, KeyCode::F(1));
+        assert!(matches!(app.mode, AppMode::CheatSheet));
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.mode, AppMode::Normal));
+    }
+
+    #[test]
+    fn overdue_view_shows_the_most_overdue_first_and_toggles_back() {
+        let mut todos = numbered(&["later", "oldest", "done", "future", "recent"]);
+        let today = Local::now().date_naive();
+        let days_ago = |n| Some(today - chrono::Duration::days(n));
+        todos[0].due = days_ago(2);
+        todos[1].due = days_ago(9);
+        todos[2].due = days_ago(20);
+        todos[2].toggle_completion();
+        todos[3].due = Some(today + chrono::Duration::days(3));
+        todos[4].due = days_ago(1);
+        let mut app = test_app(todos);
+        app.cycle_filter();
+        let filter = app.filter;
+
+        press(&mut app, KeyCode::Char('!'));
+        let shown: Vec<&str> = app
+            .visible_indices()
+            .into_iter()
+            .map(|i| app.todos[i].text.as_str())
+            .collect();
+        assert_eq!(shown, vec!["oldest", "later", "recent"]);
+
+        press(&mut app, KeyCode::Char('!'));
+        assert!(app.overdue_view.is_none());
+        assert_eq!(app.filter, filter);
+        assert_eq!(app.due_window, DueWindow::Any);
+        assert_eq!(app.visible_indices().len(), 4);
+    }
+
+    #[test]
+    fn todos_remember_where_they_were_added() {
+        let mut app = test_app(Vec::new());
+        app.add_todo("typed".to_string());
+        app.add_todo_from("scripted", Source::Cli).unwrap();
+        let mut imported = TodoItem::new(0, "imported".to_string());
+        imported.source = Some(Source::Import);
+        app.append_todos(vec![imported]).unwrap();
+
+        for (source, text) in [
+            ("tui", "typed"),
+            ("cli", "scripted"),
+            ("IMPORT", "imported"),
+        ] {
+            let query = SearchQuery::parse(&format!("source:{}", source), TagCombinator::And);
+            let found: Vec<&str> = app
+                .todos
+                .iter()
+                .filter(|t| query.matches(t))
+                .map(|t| t.text.as_str())
+                .collect();
+            assert_eq!(found, vec![text]);
+        }
+    }
+
+    #[test]
+    fn merging_renumbers_and_skips_duplicates() {
+        let mut app = test_app(numbered(&["milk", "eggs"]));
+        let mut other = numbered(&["Milk", "trip", "pack"]);
+        other[2].parent = Some(2);
+
+        let (merged, skipped) = app.merge_todos(other.clone(), true).unwrap();
+        assert_eq!((merged, skipped), (2, 1));
+        assert_eq!(texts(&app), vec!["milk", "eggs", "trip", "pack"]);
+        assert_eq!(app.todos[3].id, 4);
+        assert_eq!(app.todos[3].parent, Some(3));
+
+        let (merged, skipped) = app.merge_todos(other, false).unwrap();
+        assert_eq!((merged, skipped), (3, 0));
+        let ids: HashSet<usize> = app.todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids.len(), 7);
+    }
+
+    #[test]
+    fn brackets_cycle_through_overdue_todos_only() {
+        let mut todos = numbered(&["late", "fine", "later", "undated"]);
+        let today = Local::now().date_naive();
+        todos[0].due = Some(today - chrono::Duration::days(1));
+        todos[1].due = Some(today + chrono::Duration::days(1));
+        todos[2].due = Some(today - chrono::Duration::days(5));
+        let mut app = test_app(todos);
+
+        press(&mut app, KeyCode::Char(']'));
+        assert_eq!(selected_text(&app), Some("later"));
+        press(&mut app, KeyCode::Char(']'));
+        assert_eq!(selected_text(&app), Some("late"));
+        press(&mut app, KeyCode::Char('['));
+        assert_eq!(selected_text(&app), Some("later"));
+
+        app.todos[0].due = None;
+        app.todos[2].toggle_completion();
+        press(&mut app, KeyCode::Char(']'));
+        assert_eq!(app.message.as_deref(), Some("No overdue todos"));
+    }
+
+    #[test]
+    fn backups_are_pruned_and_restorable_when_gzipped() {
+        let mut app = test_app(numbered(&["original"]));
+        // The temp dir is named by pid, so a past run may have left a list
+        // or backups behind, which would be backed up or counted here
+        let dir = Path::new(&app.data_file).with_file_name("backups");
+        let _ = fs::remove_file(&app.data_file);
+        let _ = fs::remove_dir_all(&dir);
+        app.config.backup_count = 2;
+        app.config.backup_compress = true;
+        app.save_todos().unwrap();
+        assert!(app.backups().is_empty(), "nothing to back up yet");
+
+        // Backups are named by the second, so fake older ones to prune
+        fs::create_dir_all(&dir).unwrap();
+        for stamp in ["20200101-000000", "20200102-000000"] {
+            fs::write(dir.join(format!("todos-{}.json", stamp)), "[]").unwrap();
+        }
+        fs::write(dir.join("todos-work-20200101-000000.json"), "[]").unwrap();
+
+        app.backed_up = false;
+        app.add_todo("changed".to_string());
+        let backups = app.backups();
+        assert_eq!(backups.len(), 2);
+        assert!(backups[0].ends_with("todos-20200102-000000.json"));
+        assert!(backups[1].to_string_lossy().ends_with(".json.gz"));
+        assert!(dir.join("todos-work-20200101-000000.json").exists());
+
+        let name = backups[1]
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        assert_eq!(app.restore_backup(&name).unwrap(), 1);
+        assert_eq!(texts(&app), vec!["original"]);
+    }
+
+    #[test]
+    fn completing_can_log_a_note_or_skip_it() {
+        let mut app = test_app(numbered(&["write report", "call mum"]));
+        app.config.ask_completion_note = true;
+
+        press(&mut app, KeyCode::Char(' '));
+        assert!(matches!(app.mode, AppMode::Prompt));
+        type_text(&mut app, "sent to Sam");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.todos[0].completion_note.as_deref(), Some("sent to Sam"));
+
+        app.select_by_id(2);
+        press_all(&mut app, &[KeyCode::Char(' '), KeyCode::Enter]);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert!(app.todos[1].is_completed());
+        assert_eq!(app.todos[1].completion_note, None);
+
+        app.select_by_id(1);
+        press(&mut app, KeyCode::Char(' '));
+        assert_eq!(app.todos[0].completion_note, None);
+    }
+
+    #[test]
+    fn enter_on_empty_input_exits_or_stays_as_configured() {
+        let mut app = test_app(numbered(&["a"]));
+        press(&mut app, KeyCode::Char('i'));
+        type_text(&mut app, "   ");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.mode, AppMode::Normal));
+
+        app.config.empty_enter = EmptyEnter::Stay;
+        press(&mut app, KeyCode::Char('i'));
+        type_text(&mut app, "  ");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.mode, AppMode::Insert));
+        assert_eq!(app.input.value(), "");
+        assert_eq!(app.todos.len(), 1);
+
+        // An empty line still ends rapid entry
+        press(&mut app, KeyCode::Esc);
+        press_all(&mut app, &[KeyCode::Char('I'), KeyCode::Enter]);
+        assert!(matches!(app.mode, AppMode::Normal));
+    }
+
+    #[test]
+    fn duplicates_are_merged_into_the_first_copy() {
+        let mut todos = numbered(&["Buy milk", "eggs", "buy  MILK", "eggs", "bread"]);
+        todos[2].tags = vec!["shop".to_string()];
+        todos[2].priority = Some(Priority::High);
+        todos[2].notes = "semi-skimmed".to_string();
+        let mut app = test_app(todos);
+
+        app.open_duplicates();
+        assert!(matches!(app.mode, AppMode::Duplicates));
+        assert_eq!(app.duplicates, vec![vec![1, 3], vec![2, 4]]);
+
+        press(&mut app, KeyCode::Char('m'));
+        let milk = &app.todos[0];
+        assert_eq!(milk.tags, vec!["shop"]);
+        assert_eq!(milk.priority, Some(Priority::High));
+        assert_eq!(milk.notes, "semi-skimmed");
+        assert_eq!(app.trash[0].todo.id, 3);
+
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(texts(&app), vec!["Buy milk", "eggs", "bread"]);
+        assert!(matches!(app.mode, AppMode::Normal));
+    }
+
+    #[test]
+    fn merging_into_a_subtask_of_a_copy_moves_it_to_the_top() {
+        let mut todos = numbered(&["eggs", "eggs", "crack them"]);
+        todos[0].parent = Some(2);
+        todos[2].parent = Some(2);
+        let mut app = test_app(todos);
+
+        app.open_duplicates();
+        assert_eq!(app.duplicates, vec![vec![1, 2]]);
+        press(&mut app, KeyCode::Char('m'));
+
+        assert_eq!(texts(&app), vec!["eggs", "crack them"]);
+        assert_eq!(app.todos[0].parent, None);
+        assert_eq!(app.todos[1].parent, Some(1));
+    }
+
+    #[test]
+    fn normalize_ids_renumbers_duplicates_and_zero() {
+        let mut todos: Vec<TodoItem> = [3, 1, 3, 0, 2]
+            .into_iter()
+            .map(|id| TodoItem::new(id, format!("todo {}", id)))
+            .collect();
+
+        assert_eq!(App::normalize_ids(&mut todos), 2);
+        let ids: Vec<usize> = todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![3, 1, 4, 5, 2]);
+    }
+
+    fn day(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn recurring_todo_resets_on_its_end_date() {
+        let mut todo = completed_recurring(1, "stretch");
+        todo.recur_until = Some(day("2026-03-10"));
+        let mut todos = vec![todo];
+
+        assert!(App::reset_recurring(
+            &mut todos,
+            Some(day("2026-03-09")),
+            day("2026-03-10")
+        ));
+        assert!(!todos[0].is_completed());
+    }
+
+    #[test]
+    fn recurring_todo_stops_after_its_end_date() {
+        let mut todo = completed_recurring(1, "stretch");
+        todo.recur_until = Some(day("2026-03-10"));
+        let mut todos = vec![todo];
+
+        assert!(!App::reset_recurring(
+            &mut todos,
+            Some(day("2026-03-10")),
+            day("2026-03-11")
+        ));
+        assert!(todos[0].is_completed());
+    }
+
+    #[test]
+    fn uncompleting_recurring_todo_asks_for_confirmation() {
+        let mut app = test_app(vec![completed_recurring(1, "stretch")]);
+
+        press(&mut app, KeyCode::Char(' '));
+        assert!(matches!(app.mode, AppMode::Confirm));
+        assert!(app.todos[0].is_completed());
+
+        press(&mut app, KeyCode::Char('n'));
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert!(app.todos[0].is_completed());
+    }
+
+    #[test]
+    fn confirming_uncomplete_only_reverts_recurring_todo() {
+        let mut app = test_app(vec![completed_recurring(1, "stretch")]);
+
+        press(&mut app, KeyCode::Char(' '));
+        press(&mut app, KeyCode::Char('y'));
+
+        assert_eq!(app.todos.len(), 1);
+        assert_eq!(app.todos[0].id, 1);
+        assert!(!app.todos[0].is_completed());
+        assert!(app.todos[0].is_recurring());
+    }
+
+    #[test]
+    fn completing_recurring_todo_does_not_duplicate_it() {
+        let mut todo = TodoItem::new(1, "stretch".to_string());
+        todo.toggle_daily_recurrence();
+        let mut app = test_app(vec![todo]);
+
+        press(&mut app, KeyCode::Char(' '));
+
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(app.todos.len(), 1);
+        assert!(app.todos[0].is_completed());
     }
 }
@@ -0,0 +1,103 @@
+use crate::logger;
+use chrono::{Local, NaiveDateTime};
+use color_eyre::{Result, eyre::eyre};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Timestamp in backup file names. It sorts in the order backups were taken.
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Backups live in `backups/` next to the list files, named after the list
+/// and the time they were taken, e.g. `todos-20261017-093000.json.gz`.
+fn backup_dir(data_file: &Path) -> PathBuf {
+    data_file.with_file_name("backups")
+}
+
+/// Copies `data_file` into the backup directory, gzipped if `compress` is
+/// set, then deletes the oldest backups of the list beyond `keep`.
+pub fn create(data_file: &Path, keep: usize, compress: bool) -> Result<PathBuf> {
+    let dir = backup_dir(data_file);
+    fs::create_dir_all(&dir)?;
+
+    let stem = data_file.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = data_file.extension().unwrap_or_default().to_string_lossy();
+    let stamp = Local::now().format(TIMESTAMP_FORMAT);
+    let content = fs::read(data_file)?;
+    let path = if compress {
+        let path = dir.join(format!("{}-{}.{}.gz", stem, stamp, ext));
+        let mut encoder = GzEncoder::new(fs::File::create(&path)?, Compression::default());
+        encoder.write_all(&content)?;
+        encoder.finish()?;
+        path
+    } else {
+        let path = dir.join(format!("{}-{}.{}", stem, stamp, ext));
+        fs::write(&path, content)?;
+        path
+    };
+    logger::debug!("backed up {} to {}", data_file.display(), path.display());
+
+    let backups = list(data_file);
+    for old in &backups[..backups.len().saturating_sub(keep)] {
+        logger::debug!("pruning backup {}", old.display());
+        fs::remove_file(old)?;
+    }
+    Ok(path)
+}
+
+/// The backups of `data_file`, oldest first.
+pub fn list(data_file: &Path) -> Vec<PathBuf> {
+    let stem = data_file.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = data_file.extension().unwrap_or_default().to_string_lossy();
+    let prefix = format!("{}-", stem);
+    let (plain, gzipped) = (format!(".{}", ext), format!(".{}.gz", ext));
+
+    let mut backups: Vec<(NaiveDateTime, PathBuf)> = fs::read_dir(backup_dir(data_file))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            // Other lists can share the prefix, e.g. `work-home` for `work`,
+            // so the rest of the name has to be exactly a timestamp
+            let name = path.file_name()?.to_str()?;
+            let rest = name.strip_prefix(&prefix)?;
+            let stamp = rest
+                .strip_suffix(&gzipped)
+                .or_else(|| rest.strip_suffix(&plain))?;
+            let taken = NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT).ok()?;
+            Some((taken, path))
+        })
+        .collect();
+    backups.sort();
+    backups.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Reads a backup, unpacking it if it is gzipped.
+pub fn read(backup: &Path) -> Result<String> {
+    let bytes = fs::read(backup)?;
+    if backup.extension().is_some_and(|ext| ext == "gz") {
+        let mut content = String::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_string(&mut content)
+            .map_err(|err| eyre!("could not unpack {}: {}", backup.display(), err))?;
+        Ok(content)
+    } else {
+        Ok(String::from_utf8(bytes)?)
+    }
+}
+
+/// Finds a backup given as a path, or as a file name in the backup
+/// directory of `data_file`.
+pub fn find(data_file: &Path, name: &str) -> Result<PathBuf> {
+    let path = Path::new(name);
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    let in_dir = backup_dir(data_file).join(name);
+    if in_dir.is_file() {
+        return Ok(in_dir);
+    }
+    Err(eyre!("no backup named {}", name))
+}
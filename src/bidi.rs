@@ -0,0 +1,132 @@
+use std::borrow::Cow;
+use unicode_width::UnicodeWidthChar;
+
+/// Whether `c` belongs to a right-to-left script: Hebrew, Arabic, Syriac,
+/// Thaana or NKo, including their presentation forms.
+pub fn is_rtl_char(c: char) -> bool {
+    matches!(c as u32, 0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF)
+}
+
+/// Letters and digits of left-to-right scripts. Digits keep their order
+/// inside right-to-left text, so they count as left-to-right here.
+fn is_ltr_char(c: char) -> bool {
+    c.is_alphanumeric() && !is_rtl_char(c)
+}
+
+/// Whether text reads right to left, going by its first letter.
+pub fn is_rtl(text: &str) -> bool {
+    text.chars()
+        .find(|c| c.is_alphabetic())
+        .is_some_and(is_rtl_char)
+}
+
+/// Tells whether a character has a given direction.
+type CharClass = fn(char) -> bool;
+
+/// Brackets face the other way when read right to left.
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        other => other,
+    }
+}
+
+/// Text in the order it should appear on screen, for terminals that draw
+/// characters left to right as they come.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisualLine {
+    pub text: String,
+    /// For each character of the original text, the column it is drawn at.
+    pub columns: Vec<usize>,
+    /// Columns the whole line takes up.
+    pub width: usize,
+    pub rtl: bool,
+}
+
+/// Lays out `text` for display. This is a simplified form of the Unicode
+/// bidi algorithm: runs of the opposite direction are reversed in place and
+/// right-to-left text is mirrored as a whole, which covers a language mixed
+/// with the odd word or number of another.
+pub fn layout(text: &str) -> VisualLine {
+    // Combining marks (vowel points, harakat) stay with their base letter
+    let mut clusters: Vec<(usize, String)> = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        match clusters.last_mut() {
+            Some((_, cluster)) if c.width() == Some(0) => cluster.push(c),
+            _ => clusters.push((i, c.to_string())),
+        }
+    }
+    let first = |cluster: &(usize, String)| cluster.1.chars().next().unwrap_or(' ');
+
+    let rtl = is_rtl(text);
+    let (base, opposite): (CharClass, CharClass) = if rtl {
+        (is_rtl_char, is_ltr_char)
+    } else {
+        (is_ltr_char, is_rtl_char)
+    };
+
+    // Each run of the opposite direction goes from its first to its last
+    // strong character, taking in the spaces and punctuation between
+    let mut order: Vec<usize> = (0..clusters.len()).collect();
+    let mut in_run = vec![false; clusters.len()];
+    let mut i = 0;
+    while i < clusters.len() {
+        if !opposite(first(&clusters[i])) {
+            i += 1;
+            continue;
+        }
+        let mut end = i;
+        for (j, cluster) in clusters.iter().enumerate().skip(i + 1) {
+            let c = first(cluster);
+            if base(c) {
+                break;
+            }
+            if opposite(c) {
+                end = j;
+            }
+        }
+        order[i..=end].reverse();
+        in_run[i..=end].fill(true);
+        i = end + 1;
+    }
+    if rtl {
+        // Reversing the whole line puts the runs back in reading order
+        order.reverse();
+    }
+
+    let mut line = VisualLine {
+        text: String::with_capacity(text.len()),
+        columns: vec![0; text.chars().count()],
+        width: 0,
+        rtl,
+    };
+    for index in order {
+        let (start, cluster) = &clusters[index];
+        let reversed = rtl != in_run[index];
+        for (offset, c) in cluster.chars().enumerate() {
+            line.text.push(if reversed { mirror(c) } else { c });
+            line.columns[start + offset] = line.width;
+        }
+        line.width += cluster
+            .chars()
+            .map(|c| c.width().unwrap_or(0))
+            .sum::<usize>();
+    }
+    line
+}
+
+/// `text` ready to draw, reordered only if it has right-to-left characters.
+pub fn display(text: &str) -> Cow<'_, str> {
+    if text.chars().any(is_rtl_char) {
+        Cow::Owned(layout(text).text)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
@@ -0,0 +1,328 @@
+use crate::app::App;
+use crate::config::{Config, StorageFormat};
+use crate::export::{self, ExportFormat};
+use crate::import::{self, ImportFormat};
+use crate::todo::Source;
+use chrono::{DateTime, Local, Utc};
+use color_eyre::{Result, eyre::eyre};
+use std::fs;
+use std::path::Path;
+
+/// Options parsed from the command line.
+#[derive(Debug, Default)]
+pub struct Cli {
+    pub command: Option<Command>,
+    /// File to write debug logs to, also settable through `OXITODO_LOG`.
+    pub log_file: Option<String>,
+    /// List to open instead of the configured default.
+    pub list: Option<String>,
+    /// Leave the mouse to the terminal, overriding `mouse_enabled`.
+    pub no_mouse: bool,
+}
+
+impl Cli {
+    /// Parses the command line arguments (excluding the program name).
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut log_file = std::env::var("OXITODO_LOG").ok();
+        let mut list = None;
+        let mut no_mouse = false;
+        let mut rest = Vec::new();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--log" => {
+                    let path = args.next().ok_or_else(|| eyre!("usage: --log <path>"))?;
+                    log_file = Some(path.clone());
+                }
+                "--list" => {
+                    let name = args.next().ok_or_else(|| eyre!("usage: --list <name>"))?;
+                    list = Some(name.clone());
+                }
+                "--no-mouse" => no_mouse = true,
+                _ => rest.push(arg.clone()),
+            }
+        }
+
+        let command = if rest.is_empty() {
+            None
+        } else {
+            Some(Command::parse(&rest)?)
+        };
+
+        Ok(Self {
+            command,
+            log_file,
+            list,
+            no_mouse,
+        })
+    }
+}
+
+/// Subcommands that run without starting the TUI.
+#[derive(Debug)]
+pub enum Command {
+    /// Adds a todo, written as in the TUI's quick capture.
+    Add(String),
+    /// Completes a todo by id, or by a unique case-insensitive text match.
+    Done(String),
+    /// Checks that a todos file parses, defaulting to the data file.
+    Validate(Option<String>),
+    /// Appends todos from another app's export.
+    Import { format: ImportFormat, file: String },
+    /// Appends the todos of another list file, optionally skipping ones
+    /// whose text is already in the list.
+    Merge { file: String, dedupe: bool },
+    /// Lists the current list's backups, or replaces the list with one.
+    Restore(Option<String>),
+    /// Rewrites the data files from the other storage format into this one.
+    Convert(StorageFormat),
+    /// Prints what was done, added and left overdue this week.
+    Review,
+    /// Writes the list to a file for sharing. With `visible`, only the
+    /// todos the list's saved filter shows are written.
+    Export {
+        format: ExportFormat,
+        file: String,
+        visible: bool,
+    },
+}
+
+impl Command {
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let name = args.first().map(String::as_str).unwrap_or_default();
+
+        match name {
+            "add" => {
+                let text = args[1..].join(" ");
+                if text.trim().is_empty() {
+                    return Err(eyre!("usage: oxitodo add <text>"));
+                }
+                Ok(Command::Add(text))
+            }
+            "done" => {
+                let query = args[1..].join(" ");
+                if query.trim().is_empty() {
+                    return Err(eyre!("usage: oxitodo done <id|text>"));
+                }
+                Ok(Command::Done(query))
+            }
+            "review" => Ok(Command::Review),
+            "--validate" => Ok(Command::Validate(args.get(1).cloned())),
+            "--import-format" => match (args.get(1), args.get(2)) {
+                (Some(format), Some(file)) => Ok(Command::Import {
+                    format: ImportFormat::parse(format)?,
+                    file: file.clone(),
+                }),
+                _ => Err(eyre!("usage: oxitodo --import-format <format> <file>")),
+            },
+            "--merge" => match (args.get(1), args.get(2).map(String::as_str)) {
+                (Some(file), None | Some("--dedupe")) => Ok(Command::Merge {
+                    file: file.clone(),
+                    dedupe: args.len() > 2,
+                }),
+                _ => Err(eyre!("usage: oxitodo --merge <file> [--dedupe]")),
+            },
+            "--restore" => Ok(Command::Restore(args.get(1).cloned())),
+            "--convert" => {
+                let format = args
+                    .get(1)
+                    .ok_or_else(|| eyre!("usage: oxitodo --convert <json|yaml>"))?;
+                Ok(Command::Convert(StorageFormat::parse(format)?))
+            }
+            "--export" => match (args.get(1), args.get(2), args.get(3).map(String::as_str)) {
+                (Some(format), Some(file), None | Some("--visible")) => Ok(Command::Export {
+                    format: ExportFormat::parse(format)?,
+                    file: file.clone(),
+                    visible: args.len() > 3,
+                }),
+                _ => Err(eyre!("usage: oxitodo --export <format> <file> [--visible]")),
+            },
+            other => Err(eyre!("unknown command '{}'", other)),
+        }
+    }
+}
+
+/// Opens the app for a command, passing on any warning about the list.
+fn open_app(list: Option<&str>) -> Result<App> {
+    let mut app = App::new(list, false)?;
+    // The process ends right after the command, so write changes at once
+    app.config.save_delay_ms = 0;
+    if let Some(warning) = &app.message {
+        eprintln!("Warning: {}", warning);
+    }
+    Ok(app)
+}
+
+pub fn run(command: Command, list: Option<&str>) -> Result<()> {
+    match command {
+        Command::Add(text) => {
+            let mut app = open_app(list)?;
+            if let Some(id) = app.add_todo_from(&text, Source::Cli)?
+                && let Some(todo) = app.todos.iter().find(|t| t.id == id)
+            {
+                println!("Added #{}: {}", todo.id, todo.text);
+            }
+        }
+        Command::Done(query) => {
+            let mut app = open_app(list)?;
+            // Numbers are always ids, anything else is matched against the text
+            let id = match query.trim().parse() {
+                Ok(id) => id,
+                Err(_) => app.find_pending_by_text(query.trim())?,
+            };
+            let todo = app.complete_todo_by_id(id)?;
+            println!("Completed #{}: {}", todo.id, todo.text);
+        }
+        Command::Validate(file) => {
+            let file = match file {
+                Some(file) => file,
+                None => App::get_data_file_path(Config::load()?.storage_format)?,
+            };
+            if !Path::new(&file).exists() {
+                return Err(eyre!("{} does not exist", file));
+            }
+
+            let todos =
+                App::load_todos(&file).map_err(|err| eyre!("{} is invalid: {}", file, err))?;
+            serde_json::to_string(&todos)?;
+            println!("{} is valid ({} todos)", file, todos.len());
+        }
+        Command::Import { format, file } => {
+            let imported = import::import_file(format, &file)
+                .map_err(|err| eyre!("could not import {}: {}", file, err))?;
+            let mut app = open_app(list)?;
+            let count = app.append_todos(imported.todos)?;
+            println!("Imported {} todos ({} skipped)", count, imported.skipped);
+        }
+        Command::Merge { file, dedupe } => {
+            if !Path::new(&file).exists() {
+                return Err(eyre!("{} does not exist", file));
+            }
+            let todos = App::load_list(Path::new(&file))
+                .map_err(|err| eyre!("could not read {}: {}", file, err))?;
+            let mut app = open_app(list)?;
+            let (merged, skipped) = app.merge_todos(todos, dedupe)?;
+            println!(
+                "Merged {} todos into '{}' ({} skipped as duplicates)",
+                merged,
+                app.list_name(),
+                skipped
+            );
+        }
+        Command::Restore(None) => {
+            let app = open_app(list)?;
+            let backups = app.backups();
+            if backups.is_empty() {
+                println!("No backups of '{}'", app.list_name());
+            }
+            for backup in backups {
+                println!("{}", backup.display());
+            }
+        }
+        Command::Restore(Some(name)) => {
+            let mut app = open_app(list)?;
+            let count = app.restore_backup(&name)?;
+            println!("Restored {} todos into '{}'", count, app.list_name());
+        }
+        Command::Convert(target) => convert(target)?,
+        Command::Review => print_review(&open_app(list)?),
+        Command::Export {
+            format,
+            file,
+            visible,
+        } => {
+            let app = open_app(list)?;
+            let todos = if visible {
+                app.visible_todos()
+            } else {
+                app.todos.iter().collect()
+            };
+            export::export_file(
+                format,
+                app.list_name(),
+                &todos,
+                &app.config.date_format,
+                &file,
+            )
+            .map_err(|err| eyre!("could not export to {}: {}", file, err))?;
+            println!("Exported {} todos to {}", todos.len(), file);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_review(app: &App) {
+    let date_format = &app.config.date_format;
+    let day = |at: Option<DateTime<Utc>>| {
+        at.map(|at| at.with_timezone(&Local).format(date_format).to_string())
+            .unwrap_or_default()
+    };
+
+    let review = app.weekly_review(Local::now().date_naive());
+    println!("Week of {}", review.since.format(date_format));
+
+    println!("\nCompleted ({})", review.completed.len());
+    for todo in &review.completed {
+        println!("  {}  #{} {}", day(todo.completed_at), todo.id, todo.text);
+    }
+
+    println!("\nOverdue ({})", review.overdue.len());
+    for todo in &review.overdue {
+        let due = todo.due.map(|due| due.format(date_format).to_string());
+        println!(
+            "  due {}  #{} {}",
+            due.unwrap_or_default(),
+            todo.id,
+            todo.text
+        );
+    }
+
+    println!("\nAdded ({})", review.added.len());
+    for todo in &review.added {
+        println!("  {}  #{} {}", day(todo.created_at), todo.id, todo.text);
+    }
+}
+
+/// Converts the todos, archive and recycle bin files into `target`, leaving
+/// the originals in place. Refuses to overwrite files that already exist.
+fn convert(target: StorageFormat) -> Result<()> {
+    let source = match target {
+        StorageFormat::Json => StorageFormat::Yaml,
+        StorageFormat::Yaml => StorageFormat::Json,
+    };
+    let data_file = App::get_data_file_path(source)?;
+    let data_dir = Path::new(&data_file)
+        .parent()
+        .ok_or_else(|| eyre!("{} has no parent directory", data_file))?;
+
+    let files: Vec<_> = ["todos", "archive", "trash"]
+        .into_iter()
+        .map(|name| data_dir.join(format!("{}.{}", name, source.extension())))
+        .filter(|file| file.exists())
+        .collect();
+    if files.is_empty() {
+        return Err(eyre!("no {} data files found", source.extension()));
+    }
+    for file in &files {
+        let converted = file.with_extension(target.extension());
+        if converted.exists() {
+            return Err(eyre!("{} already exists", converted.display()));
+        }
+    }
+
+    for file in files {
+        // Go through a generic value so fields survive the round trip as-is
+        let items: Vec<serde_json::Value> = App::load_list(&file)?;
+        let converted = file.with_extension(target.extension());
+        fs::write(&converted, target.serialize(&items, false)?)?;
+        println!("Converted {} -> {}", file.display(), converted.display());
+    }
+    println!(
+        "Set \"storage_format\": \"{}\" in the config to use the new files",
+        target.extension()
+    );
+
+    Ok(())
+}
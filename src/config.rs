@@ -0,0 +1,467 @@
+use crate::logger;
+use crate::todo::Priority;
+use chrono::NaiveDate;
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use ratatui::text::Line;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// User settings read from `config.json`. Every field is optional in the
+/// file; missing ones fall back to their defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Start with completed todos filtered out of the list.
+    pub hide_completed: bool,
+    /// Ask for a one-line note when completing a todo in the TUI. An empty
+    /// answer skips it.
+    pub ask_completion_note: bool,
+    /// Completing a todo whose subtasks are folded away also completes
+    /// them, so nothing unfinished is left out of sight.
+    pub complete_hidden_subtasks: bool,
+    /// Priority given to new todos that don't set one with `!priority`.
+    pub default_priority: Option<Priority>,
+    /// Number of days the snooze action pushes a due date forward.
+    pub snooze_days: u32,
+    /// Write the todos file without pretty-printing to keep it small. Only
+    /// applies to JSON storage.
+    pub compact_storage: bool,
+    /// File format the todos, archive and recycle bin are stored in.
+    pub storage_format: StorageFormat,
+    /// Back up a list before it is first changed in a session, keeping
+    /// this many backups per list. Zero turns backups off.
+    pub backup_count: usize,
+    /// Gzip backups to save space.
+    pub backup_compress: bool,
+    /// Stay in Insert mode after adding a todo so several can be typed in a
+    /// row. The `I` key does this for a single session either way.
+    pub rapid_entry: bool,
+    /// What Enter does in Insert mode when nothing was typed. In rapid
+    /// entry an empty line always finishes.
+    pub empty_enter: EmptyEnter,
+    /// Keep completed todos after the active ones whatever the sort mode.
+    pub completed_to_bottom: bool,
+    /// Which todo to select after deleting one. `next` and `previous` follow
+    /// the todo even when deleting a parent moves rows around.
+    pub after_delete_focus: DeleteFocus,
+    /// Where todos without a due date go when sorting by due date, in
+    /// either direction. Defaults to `last`.
+    pub no_due_position: UnsetPosition,
+    /// Where todos without a priority go when sorting by priority, in
+    /// either direction. Defaults to `last`.
+    pub no_priority_position: UnsetPosition,
+    /// Re-sort after every change while a sort mode other than the default
+    /// is active, instead of only when the sort is changed.
+    pub auto_sort: bool,
+    /// Show a header above each tag's todos when sorting by tag.
+    pub group_by_tag: bool,
+    /// List todos with the same text when the app starts, to merge or
+    /// delete the extra copies.
+    pub report_duplicates: bool,
+    /// Draw right-to-left text (Hebrew, Arabic) in display order. Turn off
+    /// on terminals that reorder it themselves.
+    pub bidi: bool,
+    /// Show a summary of the day's todos when the app starts.
+    pub show_dashboard: bool,
+    /// How the status bar fits narrow terminals: `drop` leaves out its less
+    /// important parts, `truncate` cuts it off at the end.
+    pub status_overflow: StatusOverflow,
+    /// Cheer in the free space under the list once every todo is done.
+    pub celebrate_all_done: bool,
+    /// Start in Insert mode when there are no todos yet, so the first
+    /// action is just typing.
+    pub insert_on_empty: bool,
+    /// Move todos completed more than this many days ago into the archive
+    /// on startup. Zero disables auto-archiving.
+    pub auto_archive_days: u32,
+    /// How many deleted todos the recycle bin keeps.
+    pub trash_limit: usize,
+    /// Shell command run whenever a todo is completed, with the todo id and
+    /// text appended as arguments. It runs with your permissions, so only
+    /// point it at commands you trust.
+    pub on_complete_cmd: Option<String>,
+    /// Quit after this many seconds without any input, for shared
+    /// terminals. Zero disables the timeout.
+    pub idle_timeout_secs: u64,
+    /// Longest wait for input before the screen is redrawn, in
+    /// milliseconds. Lower feels snappier, higher uses less CPU.
+    pub poll_interval_ms: u64,
+    /// How often timers, the undo offer, due notifications and file changes
+    /// are checked, in milliseconds, whether or not keys are pressed.
+    pub tick_interval_ms: u64,
+    /// Wait this many milliseconds after a change before writing the list,
+    /// so a burst of edits is written once. Anything pending is written on
+    /// quit and before switching lists. Zero writes every change at once.
+    pub save_delay_ms: u64,
+    /// Show a desktop notification when a todo with a due time falls due
+    /// while the app is running.
+    pub notify_due: bool,
+    /// Reload the list automatically when another program changes its file.
+    pub watch_file: bool,
+    /// Capture mouse events. Turn off to select and copy text with the mouse
+    /// as in any other terminal program; `--no-mouse` does the same once.
+    pub mouse_enabled: bool,
+    /// Which actions ask for confirmation before running.
+    pub confirm_level: ConfirmLevel,
+    /// How several `#tags` in a search combine when the search doesn't say
+    /// `AND` or `OR` itself.
+    pub tag_combinator: TagCombinator,
+    /// Show each todo's id before its text, for use with `--done` and
+    /// other commands.
+    pub show_ids: bool,
+    /// Show the list one screenful at a time with its page number in the
+    /// title, instead of scrolling.
+    pub paged: bool,
+    /// Keep the selection on the same row when changing page, instead of
+    /// moving it to the page's first todo.
+    pub page_keeps_row: bool,
+    /// List opened on startup instead of the default `todos` list. The
+    /// `--list` option overrides it.
+    pub default_list: Option<String>,
+    /// Strike through completed todos. When off they keep normal text and
+    /// are only marked by the completed symbol. The `x` key switches this
+    /// while running.
+    pub strikethrough: bool,
+    /// Sweep the strikethrough across a todo when it is completed, rather
+    /// than striking it through at once.
+    pub animate_completion: bool,
+    /// Title of the todo panel, where `{list}` stands for the list name.
+    /// The todo count is always appended. Defaults to the list name.
+    pub list_title: Option<String>,
+    /// chrono `strftime` format used when showing dates, and accepted when
+    /// typing them. Time specifiers like `%H` have nothing to show on a
+    /// date, so formats using them fall back to `%Y-%m-%d`.
+    pub date_format: String,
+    pub theme: Theme,
+}
+
+/// What Enter on an empty or whitespace-only input does in Insert mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmptyEnter {
+    /// Go back to Normal mode, as Esc does.
+    #[default]
+    Exit,
+    /// Ignore the key and keep the input open.
+    Stay,
+}
+
+/// What gives way when the status bar doesn't fit the terminal. Key
+/// hints are always dropped first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusOverflow {
+    /// Leave out the least important parts, keeping the mode and counts.
+    #[default]
+    Drop,
+    /// Keep everything in order and cut the end off with an ellipsis.
+    Truncate,
+}
+
+/// Which todo is selected after the selected one is deleted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteFocus {
+    /// Whatever ends up in the deleted todo's row, or the last row.
+    #[default]
+    Same,
+    /// The todo shown after the deleted one, or before it if there is none.
+    Next,
+    /// The todo shown before the deleted one, or after it if there is none.
+    Previous,
+}
+
+/// Where todos missing the value a sort goes by are placed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnsetPosition {
+    First,
+    #[default]
+    Last,
+}
+
+/// How many actions ask "are you sure?" first. Each level includes the
+/// ones below it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmLevel {
+    /// Never ask.
+    None,
+    /// Ask before deleting, clearing completed todos and un-completing
+    /// recurring ones.
+    #[default]
+    Destructive,
+    /// Also ask before quitting.
+    All,
+}
+
+/// Whether a todo must carry all searched tags or any one of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagCombinator {
+    #[default]
+    And,
+    Or,
+}
+
+impl TagCombinator {
+    pub fn label(self) -> &'static str {
+        match self {
+            TagCombinator::And => "AND",
+            TagCombinator::Or => "OR",
+        }
+    }
+}
+
+/// Formats the data files can be written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+impl StorageFormat {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Ok(StorageFormat::Json),
+            "yaml" | "yml" => Ok(StorageFormat::Yaml),
+            other => Err(eyre!(
+                "unknown storage format '{}' (expected json or yaml)",
+                other
+            )),
+        }
+    }
+
+    /// Picks the format from a file's extension, treating anything that
+    /// isn't YAML as JSON.
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => StorageFormat::Yaml,
+            _ => StorageFormat::Json,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            StorageFormat::Json => "json",
+            StorageFormat::Yaml => "yaml",
+        }
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(self, content: &str) -> Result<T> {
+        Ok(match self {
+            StorageFormat::Json => serde_json::from_str(content)?,
+            StorageFormat::Yaml => serde_yaml::from_str(content)?,
+        })
+    }
+
+    pub fn serialize<T: Serialize + ?Sized>(self, value: &T, compact: bool) -> Result<String> {
+        Ok(match self {
+            StorageFormat::Json if compact => serde_json::to_string(value)?,
+            StorageFormat::Json => serde_json::to_string_pretty(value)?,
+            StorageFormat::Yaml => serde_yaml::to_string(value)?,
+        })
+    }
+}
+
+/// Appearance settings, read from the `theme` section of the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Marker drawn in front of the selected todo.
+    pub highlight_symbol: String,
+    /// Label shown before the text while typing a new todo.
+    pub input_prefix: String,
+    /// Mark between the brackets of a completed todo, e.g. `x` or `●`.
+    pub completed_symbol: String,
+    /// Mark between the brackets of a todo not yet started, e.g. `○`.
+    pub pending_symbol: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            highlight_symbol: "> ".to_string(),
+            input_prefix: "New: ".to_string(),
+            completed_symbol: "✓".to_string(),
+            pending_symbol: " ".to_string(),
+        }
+    }
+}
+
+/// Widest highlight symbol accepted before alignment starts to suffer.
+const MAX_HIGHLIGHT_WIDTH: usize = 4;
+
+/// Widest input prefix accepted, so there is still room to type.
+const MAX_INPUT_PREFIX_WIDTH: usize = 12;
+
+impl Theme {
+    /// Replaces settings that would break the layout with their defaults.
+    fn validate(&mut self) {
+        if Line::from(self.highlight_symbol.as_str()).width() > MAX_HIGHLIGHT_WIDTH {
+            logger::debug!(
+                "highlight_symbol {:?} is wider than {} columns, using the default",
+                self.highlight_symbol,
+                MAX_HIGHLIGHT_WIDTH
+            );
+            self.highlight_symbol = Theme::default().highlight_symbol;
+        }
+        if Line::from(self.input_prefix.as_str()).width() > MAX_INPUT_PREFIX_WIDTH {
+            logger::debug!(
+                "input_prefix {:?} is wider than {} columns, using the default",
+                self.input_prefix,
+                MAX_INPUT_PREFIX_WIDTH
+            );
+            self.input_prefix = Theme::default().input_prefix;
+        }
+        // The status column lines up only if every mark is one column wide,
+        // like the `~` of todos in progress
+        if Line::from(self.completed_symbol.as_str()).width() != 1 {
+            logger::debug!(
+                "completed_symbol {:?} is not one column wide, using the default",
+                self.completed_symbol
+            );
+            self.completed_symbol = Theme::default().completed_symbol;
+        }
+        if Line::from(self.pending_symbol.as_str()).width() != 1 {
+            logger::debug!(
+                "pending_symbol {:?} is not one column wide, using the default",
+                self.pending_symbol
+            );
+            self.pending_symbol = Theme::default().pending_symbol;
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            hide_completed: false,
+            ask_completion_note: false,
+            complete_hidden_subtasks: true,
+            default_priority: None,
+            snooze_days: 1,
+            compact_storage: false,
+            storage_format: StorageFormat::default(),
+            backup_count: 0,
+            backup_compress: false,
+            rapid_entry: false,
+            empty_enter: EmptyEnter::default(),
+            completed_to_bottom: false,
+            after_delete_focus: DeleteFocus::default(),
+            no_due_position: UnsetPosition::default(),
+            no_priority_position: UnsetPosition::default(),
+            auto_sort: false,
+            group_by_tag: false,
+            show_dashboard: false,
+            status_overflow: StatusOverflow::default(),
+            celebrate_all_done: false,
+            bidi: true,
+            report_duplicates: false,
+            insert_on_empty: true,
+            auto_archive_days: 0,
+            trash_limit: 50,
+            on_complete_cmd: None,
+            idle_timeout_secs: 0,
+            poll_interval_ms: 100,
+            tick_interval_ms: 250,
+            save_delay_ms: 0,
+            confirm_level: ConfirmLevel::default(),
+            tag_combinator: TagCombinator::default(),
+            notify_due: false,
+            watch_file: false,
+            mouse_enabled: true,
+            show_ids: false,
+            paged: false,
+            page_keeps_row: false,
+            default_list: None,
+            strikethrough: true,
+            animate_completion: true,
+            list_title: None,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self> {
+        let config_file = Self::get_config_file_path()?;
+        if config_file.exists() {
+            let content = fs::read_to_string(&config_file)?;
+            let mut config: Self = serde_json::from_str(&content)?;
+            config.validate();
+            Ok(config)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Re-reads only the `theme` section of the config file, for applying
+    /// theme changes without a restart.
+    pub fn load_theme() -> Result<Theme> {
+        let config_file = Self::get_config_file_path()?;
+        if !config_file.exists() {
+            return Ok(Theme::default());
+        }
+        let content = fs::read_to_string(&config_file)?;
+        let mut config: serde_json::Value = serde_json::from_str(&content)?;
+        let mut theme: Theme = match config.get_mut("theme") {
+            Some(theme) => serde_json::from_value(theme.take())?,
+            None => Theme::default(),
+        };
+        theme.validate();
+        Ok(theme)
+    }
+
+    /// Replaces settings that cannot be used with their defaults.
+    pub(crate) fn validate(&mut self) {
+        // Dates have no time of day, so `%H` and the like fail to format
+        // just like a malformed specifier does
+        let mut probe = String::new();
+        let sample = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap_or_default();
+        if write!(probe, "{}", sample.format(&self.date_format)).is_err() {
+            logger::debug!(
+                "date_format {:?} is invalid, using {:?}",
+                self.date_format,
+                DEFAULT_DATE_FORMAT
+            );
+            self.date_format = DEFAULT_DATE_FORMAT.to_string();
+        }
+
+        let defaults = Config::default();
+        if self.poll_interval_ms == 0 {
+            logger::debug!("poll_interval_ms can't be 0, using the default");
+            self.poll_interval_ms = defaults.poll_interval_ms;
+        }
+        if self.tick_interval_ms == 0 {
+            logger::debug!("tick_interval_ms can't be 0, using the default");
+            self.tick_interval_ms = defaults.tick_interval_ms;
+        }
+
+        self.theme.validate();
+    }
+
+    fn get_config_file_path() -> Result<PathBuf> {
+        // Try XDG_CONFIG_HOME first, fall back to ~/.config
+        let config_dir = if let Ok(xdg_config_home) = std::env::var("XDG_CONFIG_HOME") {
+            PathBuf::from(xdg_config_home).join("oxitodo")
+        } else {
+            let home_dir = std::env::var("HOME")
+                .map_err(|_| color_eyre::eyre::eyre!("Could not find HOME directory"))?;
+
+            PathBuf::from(home_dir).join(".config").join("oxitodo")
+        };
+
+        Ok(config_dir.join("config.json"))
+    }
+}
@@ -1,46 +1,107 @@
 use crate::app::App;
+use crate::config::Config;
+use crate::logger;
 use crossterm::event::{self, Event, KeyEvent};
-use std::time::Duration;
+#[cfg(unix)]
+use signal_hook::consts::SIGHUP;
+use signal_hook::consts::SIGTERM;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
+/// Signals that save and quit. Closing the terminal sends `SIGHUP`, which
+/// only exists on Unix.
+#[cfg(unix)]
+const TERM_SIGNALS: &[i32] = &[SIGTERM, SIGHUP];
+#[cfg(not(unix))]
+const TERM_SIGNALS: &[i32] = &[SIGTERM];
+
+/// Time between redraws while an animation is running.
+const ANIMATION_FRAME: Duration = Duration::from_millis(30);
+
+/// Runs one turn of the main loop at a time: waits for input, but never past
+/// the next tick, so time-based work keeps its own cadence however often
+/// keys arrive.
 pub struct EventHandler {
-    poll_timeout: Duration,
+    poll_interval: Duration,
+    tick_interval: Duration,
+    next_tick: Instant,
+    /// Set when the process is asked to terminate.
+    terminate: Arc<AtomicBool>,
 }
 
 impl EventHandler {
-    pub fn new() -> Self {
+    pub fn new(config: &Config) -> Self {
+        let terminate = Arc::new(AtomicBool::new(false));
+        for &signal in TERM_SIGNALS {
+            if let Err(err) = signal_hook::flag::register(signal, Arc::clone(&terminate)) {
+                logger::debug!("could not handle signal {}: {}", signal, err);
+            }
+        }
+
         Self {
-            poll_timeout: Duration::from_millis(100),
+            poll_interval: Duration::from_millis(config.poll_interval_ms),
+            tick_interval: Duration::from_millis(config.tick_interval_ms),
+            next_tick: Instant::now(),
+            terminate,
         }
     }
 
-    pub fn handle_events(&self, app: &mut App) -> color_eyre::Result<()> {
-        if event::poll(self.poll_timeout)? {
-            match event::read()? {
-                Event::Key(key_event) => self.handle_key_event(app, key_event),
-                Event::Mouse(_) => {
-                    // Mouse events are currently not handled
-                }
-                Event::Resize(_, _) => {
-                    // Terminal resize events could be handled here if needed
-                }
-                Event::FocusGained | Event::FocusLost => {
-                    // Focus events could be handled here if needed
-                }
-                Event::Paste(_) => {
-                    // Paste events could be handled here if needed
-                }
-            }
+    /// Ticks if one is due, then waits for an event until the poll interval
+    /// or the next tick, whichever comes first. Everything else already
+    /// queued is handled too so held keys don't fall behind the redraws.
+    pub fn handle_events(&mut self, app: &mut App) -> color_eyre::Result<()> {
+        if self.terminate.load(Ordering::Relaxed) {
+            logger::debug!("terminated by signal, saving and quitting");
+            app.should_quit = true;
+            return app.flush();
+        }
+
+        let now = Instant::now();
+        if now >= self.next_tick {
+            app.tick(now);
+            // Skip ticks missed while busy rather than running them back to back
+            self.next_tick = now + self.tick_interval;
+        }
+
+        let mut timeout = self
+            .poll_interval
+            .min(self.next_tick.saturating_duration_since(now));
+        if app.is_animating(now) {
+            timeout = timeout.min(ANIMATION_FRAME);
+        }
+        if !event::poll(timeout)? {
+            return Ok(());
+        }
+
+        app.last_activity = Instant::now();
+        self.dispatch(app, event::read()?);
+        while !app.should_quit && event::poll(Duration::ZERO)? {
+            self.dispatch(app, event::read()?);
         }
         Ok(())
     }
 
-    fn handle_key_event(&self, app: &mut App, key_event: KeyEvent) {
-        app.handle_key_event(key_event);
+    fn dispatch(&self, app: &mut App, event: Event) {
+        match event {
+            Event::Key(key_event) => self.handle_key_event(app, key_event),
+            Event::Mouse(_) => {
+                // Mouse events are currently not handled
+            }
+            Event::Resize(width, height) => {
+                // The main loop redraws once the queue is drained
+                app.handle_resize(width, height);
+            }
+            Event::FocusGained | Event::FocusLost => {
+                // Focus events could be handled here if needed
+            }
+            Event::Paste(_) => {
+                // Paste events could be handled here if needed
+            }
+        }
     }
-}
 
-impl Default for EventHandler {
-    fn default() -> Self {
-        Self::new()
+    fn handle_key_event(&self, app: &mut App, key_event: KeyEvent) {
+        app.handle_key_event(key_event);
     }
 }
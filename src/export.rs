@@ -0,0 +1,232 @@
+use crate::todo::{Priority, TodoItem};
+use chrono::Local;
+use color_eyre::{Result, eyre::eyre};
+use std::fs;
+use std::path::Path;
+
+/// Formats accepted by `--export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A self-contained, printer-friendly HTML checklist.
+    Html,
+    /// A Markdown task list, for pasting into chats and notes.
+    Markdown,
+    /// Plain-text `todo.txt`, one todo per line.
+    TodoTxt,
+    /// A spreadsheet with a header row, as read by `--import-format csv`.
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "html" => Ok(ExportFormat::Html),
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "todotxt" | "todo.txt" | "txt" => Ok(ExportFormat::TodoTxt),
+            "csv" => Ok(ExportFormat::Csv),
+            other => Err(eyre!("unknown export format '{}'", other)),
+        }
+    }
+
+    /// Picks the format from a file's extension.
+    pub fn for_path(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        Self::parse(&extension.unwrap_or_default().to_lowercase())
+    }
+}
+
+pub fn export_file(
+    format: ExportFormat,
+    title: &str,
+    todos: &[&TodoItem],
+    date_format: &str,
+    file_path: &str,
+) -> Result<()> {
+    fs::write(file_path, render(format, title, todos, date_format)?)?;
+    Ok(())
+}
+
+/// The todos written out in `format`.
+pub fn render(
+    format: ExportFormat,
+    title: &str,
+    todos: &[&TodoItem],
+    date_format: &str,
+) -> Result<String> {
+    Ok(match format {
+        ExportFormat::Html => to_html(title, todos, date_format),
+        ExportFormat::Markdown => to_markdown(title, todos, date_format),
+        ExportFormat::TodoTxt => to_todotxt(todos),
+        ExportFormat::Csv => to_csv(todos)?,
+    })
+}
+
+/// Inline so the file can be mailed or opened anywhere on its own. Kept in
+/// black and grey so it prints well.
+const HTML_STYLE: &str = "
+body { font-family: sans-serif; color: #000; max-width: 40em; margin: 2em auto; }
+h1 { font-size: 1.4em; border-bottom: 1px solid #000; padding-bottom: 0.2em; }
+ul { list-style: none; padding: 0; }
+li { padding: 0.3em 0; border-bottom: 1px solid #ddd; }
+.box { display: inline-block; width: 1.2em; }
+.done .text { text-decoration: line-through; color: #777; }
+.priority { font-weight: bold; margin-right: 0.3em; }
+.tag, .due { color: #555; font-size: 0.9em; margin-left: 0.5em; }
+";
+
+fn to_html(title: &str, todos: &[&TodoItem], date_format: &str) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n<ul>\n",
+        escape(title),
+        HTML_STYLE,
+        escape(title)
+    );
+
+    for todo in todos {
+        let (class, check) = if todo.is_completed() {
+            ("done", "&#9745;")
+        } else {
+            ("open", "&#9744;")
+        };
+        html.push_str(&format!(
+            "<li class=\"{}\"><span class=\"box\">{}</span>",
+            class, check
+        ));
+        if let Some(priority) = todo.priority {
+            html.push_str(&format!(
+                "<span class=\"priority\">[{}]</span>",
+                priority.label()
+            ));
+        }
+        html.push_str(&format!(
+            "<span class=\"text\">{}</span>",
+            escape(&todo.text)
+        ));
+        for tag in &todo.tags {
+            html.push_str(&format!("<span class=\"tag\">#{}</span>", escape(tag)));
+        }
+        if let Some(due) = todo.due {
+            let mut label = due.format(date_format).to_string();
+            if let Some(time) = todo.due_time {
+                label.push_str(&time.format(" %H:%M").to_string());
+            }
+            html.push_str(&format!(
+                "<span class=\"due\">due {}</span>",
+                escape(&label)
+            ));
+        }
+        html.push_str("</li>\n");
+    }
+
+    html.push_str("</ul>\n</body>\n</html>\n");
+    html
+}
+
+fn to_markdown(title: &str, todos: &[&TodoItem], date_format: &str) -> String {
+    let mut markdown = format!("# {}\n\n", title);
+    for todo in todos {
+        let check = if todo.is_completed() { "x" } else { " " };
+        markdown.push_str(&format!("- [{}] ", check));
+        if let Some(priority) = todo.priority {
+            markdown.push_str(&format!("**{}** ", priority.label()));
+        }
+        markdown.push_str(&todo.text);
+        for tag in &todo.tags {
+            markdown.push_str(&format!(" #{}", tag));
+        }
+        if let Some(due) = todo.due {
+            markdown.push_str(&format!(" (due {}", due.format(date_format)));
+            if let Some(time) = todo.due_time {
+                markdown.push_str(&time.format(" %H:%M").to_string());
+            }
+            markdown.push(')');
+        }
+        markdown.push('\n');
+    }
+    markdown
+}
+
+/// Writes todos the way `--import-format todotxt` reads them back: done
+/// marker and dates first, the priority as `(A)`, or `pri:A` once done as
+/// todo.txt tools do, then the text, context, tags and `due:` date.
+fn to_todotxt(todos: &[&TodoItem]) -> String {
+    let day =
+        |at: chrono::DateTime<chrono::Utc>| at.with_timezone(&Local).format("%Y-%m-%d").to_string();
+    let letter = |priority: Priority| match priority {
+        Priority::High => "A",
+        Priority::Medium => "B",
+        Priority::Low => "C",
+    };
+
+    let mut content = String::new();
+    for todo in todos {
+        let mut words = Vec::new();
+        // The format has no place for a creation date without the
+        // completion date before it
+        let mut dated = true;
+        if todo.is_completed() {
+            words.push("x".to_string());
+            match todo.completed_at {
+                Some(at) => words.push(day(at)),
+                None => dated = false,
+            }
+        } else if let Some(priority) = todo.priority {
+            words.push(format!("({})", letter(priority)));
+        }
+        if let Some(at) = todo.created_at.filter(|_| dated) {
+            words.push(day(at));
+        }
+        words.push(todo.text.clone());
+        words.extend(todo.context.clone());
+        words.extend(todo.tags.iter().map(|tag| format!("#{}", tag)));
+        if let Some(due) = todo.due {
+            words.push(format!("due:{}", due.format("%Y-%m-%d")));
+        }
+        if let Some(priority) = todo.priority.filter(|_| todo.is_completed()) {
+            words.push(format!("pri:{}", letter(priority)));
+        }
+        content.push_str(&words.join(" "));
+        content.push('\n');
+    }
+    content
+}
+
+/// One row per todo under a `text,completed,priority,due,tags` header.
+/// Dates are ISO so spreadsheets and the importer read them the same way
+/// whatever `date_format` is.
+fn to_csv(todos: &[&TodoItem]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["text", "completed", "priority", "due", "tags"])?;
+    for todo in todos {
+        let due = todo
+            .due
+            .map(|due| due.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        writer.write_record([
+            todo.text.as_str(),
+            if todo.is_completed() { "true" } else { "false" },
+            todo.priority.map(Priority::label).unwrap_or_default(),
+            due.as_str(),
+            todo.tags.join(" ").as_str(),
+        ])?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|err| eyre!("could not write csv: {}", err))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
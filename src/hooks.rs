@@ -0,0 +1,144 @@
+use crate::logger;
+use crate::todo::TodoItem;
+use color_eyre::{Result, eyre::eyre};
+use notify_rust::Notification;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Runs the user's `on_complete_cmd` for a todo that was just completed.
+///
+/// The command is run through `sh -c` with the todo id and text appended as
+/// positional arguments (also exported as `OXITODO_ID`/`OXITODO_TEXT`), so
+/// the text is never interpolated into the command string itself. It runs in
+/// the background with its output discarded; failures only go to the log.
+pub fn on_complete(command: &str, todo: &TodoItem) {
+    let spawned = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", command))
+        .arg("oxitodo")
+        .arg(todo.id.to_string())
+        .arg(&todo.text)
+        .env("OXITODO_ID", todo.id.to_string())
+        .env("OXITODO_TEXT", &todo.text)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    match spawned {
+        Ok(mut child) => {
+            logger::debug!("ran on_complete_cmd for todo {}", todo.id);
+            // Reap the child without blocking the UI
+            std::thread::spawn(move || match child.wait() {
+                Ok(status) if !status.success() => {
+                    logger::debug!("on_complete_cmd exited with {}", status);
+                }
+                Err(err) => logger::debug!("on_complete_cmd failed: {}", err),
+                Ok(_) => {}
+            });
+        }
+        Err(err) => logger::debug!("could not run on_complete_cmd: {}", err),
+    }
+}
+
+/// Opens `url` with the platform's default handler in the background.
+pub fn open_url(url: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // Not `cmd /C start`, which would run anything after a `&` in the url
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let spawned = command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    match spawned {
+        Ok(mut child) => {
+            logger::debug!("opening {}", url);
+            std::thread::spawn(move || {
+                if let Err(err) = child.wait() {
+                    logger::debug!("url opener failed: {}", err);
+                }
+            });
+        }
+        Err(err) => logger::debug!("could not open {}: {}", url, err),
+    }
+}
+
+/// Clipboard programs tried in order, with their arguments. Wayland's comes
+/// first since `xclip` may also be installed there but reach only X apps.
+const CLIPBOARD_TOOLS: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
+    &[("pbcopy", &[])]
+} else if cfg!(windows) {
+    &[("clip", &[])]
+} else {
+    &[
+        ("wl-copy", &[]),
+        ("xclip", &["-selection", "clipboard"]),
+        ("xsel", &["--clipboard", "--input"]),
+    ]
+};
+
+/// Puts `text` on the system clipboard by piping it to the first clipboard
+/// program that is installed.
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    for (program, args) in CLIPBOARD_TOOLS {
+        let spawned = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = spawned else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take()
+            && let Err(err) = stdin.write_all(text.as_bytes())
+        {
+            logger::debug!("could not write to {}: {}", program, err);
+            // Close the pipe so the tool can exit before it is reaped
+            drop(stdin);
+            let _ = child.wait();
+            continue;
+        }
+        // The tools fork to keep serving the clipboard, so this is quick
+        let status = child.wait()?;
+        if status.success() {
+            logger::debug!("copied {} bytes with {}", text.len(), program);
+            return Ok(());
+        }
+        logger::debug!("{} exited with {}", program, status);
+    }
+    let names: Vec<&str> = CLIPBOARD_TOOLS
+        .iter()
+        .map(|(program, _)| *program)
+        .collect();
+    Err(eyre!("no clipboard available (tried {})", names.join(", ")))
+}
+
+/// Shows a desktop notification that a todo is due. Sending happens on a
+/// background thread since it can block on the session bus; systems without
+/// a notification daemon only get a log entry.
+pub fn notify_due(todo: &TodoItem) {
+    let id = todo.id;
+    let text = todo.text.clone();
+    std::thread::spawn(move || {
+        let shown = Notification::new()
+            .appname("oxitodo")
+            .summary("Todo due")
+            .body(&text)
+            .show();
+        match shown {
+            Ok(_) => logger::debug!("sent due notification for todo {}", id),
+            Err(err) => logger::debug!("could not send due notification: {}", err),
+        }
+    });
+}
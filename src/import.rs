@@ -0,0 +1,292 @@
+use crate::todo::{Priority, Source, TodoItem, is_context};
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use color_eyre::{Result, eyre::eyre};
+use serde::Deserialize;
+use std::fs;
+
+/// External formats accepted by `--import-format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// Todoist/Things-style JSON exports.
+    Todoist,
+    /// Plain-text `todo.txt` files, one todo per line.
+    TodoTxt,
+    /// Spreadsheets saved as CSV, with a header row naming the columns.
+    Csv,
+}
+
+impl ImportFormat {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "todoist" | "things" => Ok(ImportFormat::Todoist),
+            "todotxt" | "todo.txt" => Ok(ImportFormat::TodoTxt),
+            "csv" => Ok(ImportFormat::Csv),
+            other => Err(eyre!("unknown import format '{}'", other)),
+        }
+    }
+}
+
+/// Result of reading an external file: the todos that could be mapped and
+/// how many entries were skipped.
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub todos: Vec<TodoItem>,
+    pub skipped: usize,
+}
+
+pub fn import_file(format: ImportFormat, file_path: &str) -> Result<Imported> {
+    let content = fs::read_to_string(file_path)?;
+    match format {
+        ImportFormat::Todoist => parse_todoist(&content),
+        ImportFormat::TodoTxt => Ok(parse_todotxt(&content)),
+        ImportFormat::Csv => parse_csv(&content),
+    }
+}
+
+/// Accepts either a bare array of tasks or an object wrapping one.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TodoistExport {
+    Tasks(Vec<TodoistTask>),
+    Wrapped {
+        #[serde(alias = "items", alias = "todos")]
+        tasks: Vec<TodoistTask>,
+    },
+}
+
+#[derive(Deserialize)]
+struct TodoistTask {
+    #[serde(default, alias = "content", alias = "name", alias = "text")]
+    title: String,
+    #[serde(default, alias = "checked", alias = "is_completed", alias = "done")]
+    completed: bool,
+    #[serde(default)]
+    due: Option<TodoistDue>,
+}
+
+/// Todoist nests the date in an object, Things uses a plain string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TodoistDue {
+    Date(String),
+    Object { date: String },
+}
+
+impl TodoistDue {
+    fn date(&self) -> Option<NaiveDate> {
+        let (TodoistDue::Date(date) | TodoistDue::Object { date }) = self;
+        // Datetimes start with the date, so only the first ten characters matter
+        NaiveDate::parse_from_str(date.get(..10)?, "%Y-%m-%d").ok()
+    }
+}
+
+fn parse_todoist(content: &str) -> Result<Imported> {
+    let export: TodoistExport = serde_json::from_str(content)?;
+    let tasks = match export {
+        TodoistExport::Tasks(tasks) | TodoistExport::Wrapped { tasks } => tasks,
+    };
+
+    let mut imported = Imported::default();
+    for task in tasks {
+        let title = task.title.trim();
+        if title.is_empty() {
+            imported.skipped += 1;
+            continue;
+        }
+
+        // Ids are assigned when the todos are added to the list
+        let mut todo = TodoItem::new(0, title.to_string());
+        todo.source = Some(Source::Import);
+        if task.completed {
+            todo.toggle_completion();
+        }
+        todo.due = task.due.as_ref().and_then(TodoistDue::date);
+        imported.todos.push(todo);
+    }
+    Ok(imported)
+}
+
+/// Reads todo.txt lines such as
+/// `x 2026-10-17 2026-10-01 (A) Call mum +family @phone due:2026-10-20`.
+/// The first `+project` or `@context` becomes the context, any others stay
+/// in the text. `#word` tokens become tags, as in quick capture.
+fn parse_todotxt(content: &str) -> Imported {
+    let mut imported = Imported::default();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        match parse_todotxt_line(line) {
+            Some(todo) => imported.todos.push(todo),
+            None => imported.skipped += 1,
+        }
+    }
+    imported
+}
+
+fn parse_todotxt_line(line: &str) -> Option<TodoItem> {
+    let date = |word: &str| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok();
+    let mut words = line.split_whitespace().peekable();
+
+    let done = words.next_if_eq(&"x").is_some();
+    let mut priority = words
+        .next_if(|w| todotxt_priority(w).is_some())
+        .and_then(todotxt_priority);
+    let first_date = words.next_if(|w| date(w).is_some()).and_then(date);
+    // A completed todo's first date is when it was done, then when it was added
+    let (completed_on, created_on) = if done {
+        (
+            first_date,
+            words.next_if(|w| date(w).is_some()).and_then(date),
+        )
+    } else {
+        (None, first_date)
+    };
+
+    let mut text = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    let (mut context, mut due) = (None, None);
+    for word in words {
+        if let Some(value) = word.strip_prefix("due:").and_then(date) {
+            due = Some(value);
+        } else if let Some(value) = word
+            .strip_prefix("pri:")
+            .and_then(|p| todotxt_priority(&format!("({})", p)))
+        {
+            priority = Some(value);
+        } else if is_context(word) && context.is_none() {
+            context = Some(word.to_string());
+        } else if let Some(tag) = word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.to_string());
+            }
+        } else {
+            text.push(word);
+        }
+    }
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut todo = TodoItem::new(0, text.join(" "));
+    todo.source = Some(Source::Import);
+    todo.priority = priority;
+    todo.context = context;
+    todo.tags = tags;
+    todo.due = due;
+    todo.created_at = created_on.and_then(start_of_day);
+    if done {
+        todo.toggle_completion();
+        if let Some(at) = completed_on.and_then(start_of_day) {
+            todo.completed_at = Some(at);
+        }
+    }
+    Some(todo)
+}
+
+/// `(A)` is the highest priority. oxitodo has three levels, so everything
+/// from `(C)` down is low.
+fn todotxt_priority(word: &str) -> Option<Priority> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    match letter {
+        "A" => Some(Priority::High),
+        "B" => Some(Priority::Medium),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => {
+            Some(Priority::Low)
+        }
+        _ => None,
+    }
+}
+
+/// todo.txt only records days; they are read as local midnight.
+fn start_of_day(day: NaiveDate) -> Option<chrono::DateTime<Utc>> {
+    let local = Local
+        .from_local_datetime(&day.and_time(NaiveTime::MIN))
+        .earliest()?;
+    Some(local.with_timezone(&Utc))
+}
+
+/// Positions of the known columns in a CSV header. Only `text` is required.
+struct CsvColumns {
+    text: usize,
+    completed: Option<usize>,
+    priority: Option<usize>,
+    due: Option<usize>,
+    tags: Option<usize>,
+}
+
+impl CsvColumns {
+    fn from_header(header: &csv::StringRecord) -> Result<Self> {
+        let find = |names: &[&str]| {
+            header.iter().position(|column| {
+                names
+                    .iter()
+                    .any(|name| column.trim().eq_ignore_ascii_case(name))
+            })
+        };
+        Ok(Self {
+            text: find(&["text", "title", "task", "name"])
+                .ok_or_else(|| eyre!("the header has no text column"))?,
+            completed: find(&["completed", "done", "status"]),
+            priority: find(&["priority"]),
+            due: find(&["due", "due date", "due_date"]),
+            tags: find(&["tags", "tag"]),
+        })
+    }
+}
+
+/// Reads a spreadsheet export. Rows with a value that can't be read, such
+/// as an unknown priority or a date that isn't `YYYY-MM-DD`, are skipped.
+fn parse_csv(content: &str) -> Result<Imported> {
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+    let columns = CsvColumns::from_header(reader.headers()?)?;
+
+    let mut imported = Imported::default();
+    for row in reader.records() {
+        match row.ok().and_then(|row| csv_row(&columns, &row)) {
+            Some(todo) => imported.todos.push(todo),
+            None => imported.skipped += 1,
+        }
+    }
+    Ok(imported)
+}
+
+fn csv_row(columns: &CsvColumns, row: &csv::StringRecord) -> Option<TodoItem> {
+    let cell = |column: Option<usize>| {
+        column
+            .and_then(|i| row.get(i))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+
+    let text = cell(Some(columns.text))?;
+    let completed = match cell(columns.completed).map(str::to_lowercase).as_deref() {
+        None | Some("false" | "no" | "0" | "todo" | "open") => false,
+        Some("true" | "yes" | "1" | "x" | "done" | "completed") => true,
+        Some(_) => return None,
+    };
+    let priority = match cell(columns.priority) {
+        Some(value) => Some(Priority::parse(&value.to_lowercase())?),
+        None => None,
+    };
+    let due = match cell(columns.due) {
+        Some(value) => Some(NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?),
+        None => None,
+    };
+
+    let mut todo = TodoItem::new(0, text.to_string());
+    todo.source = Some(Source::Import);
+    todo.priority = priority;
+    todo.due = due;
+    if let Some(tags) = cell(columns.tags) {
+        for tag in tags
+            .split([' ', ',', ';'])
+            .map(|tag| tag.trim_start_matches('#'))
+        {
+            if !tag.is_empty() && !todo.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                todo.tags.push(tag.to_string());
+            }
+        }
+    }
+    if completed {
+        todo.toggle_completion();
+    }
+    Some(todo)
+}
@@ -0,0 +1,386 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Everything that can be triggered by a key in Normal mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    NextOverdue,
+    PreviousOverdue,
+    NextPage,
+    PreviousPage,
+    GotoId,
+    Add,
+    AddMany,
+    AddSubtask,
+    ToggleFold,
+    Toggle,
+    CycleStatus,
+    Delete,
+    UndoDelete,
+    ClearCompleted,
+    ToggleRecurrence,
+    SetRecurUntil,
+    SetDueDate,
+    SetDueTime,
+    Snooze,
+    ToggleTimer,
+    IncreaseProgress,
+    DecreaseProgress,
+    PriorityLow,
+    PriorityMedium,
+    PriorityHigh,
+    ClearPriority,
+    OpenTags,
+    ToggleCompact,
+    ToggleStrikethrough,
+    CycleFilter,
+    CycleDueWindow,
+    CollapseCompleted,
+    OverdueView,
+    Search,
+    CycleSort,
+    ReverseSort,
+    OpenDetail,
+    OpenLink,
+    OpenArchive,
+    OpenTrash,
+    OpenLists,
+    PreviousList,
+    Export,
+    YankList,
+    Reload,
+    ReloadTheme,
+    Help,
+    CheatSheet,
+    Quit,
+}
+
+/// Headings the help screen groups actions under, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Navigation,
+    Todos,
+    Planning,
+    View,
+    General,
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [
+        Category::Navigation,
+        Category::Todos,
+        Category::Planning,
+        Category::View,
+        Category::General,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Category::Navigation => "Navigation",
+            Category::Todos => "Todos",
+            Category::Planning => "Planning",
+            Category::View => "View",
+            Category::General => "General",
+        }
+    }
+}
+
+impl Action {
+    pub fn category(self) -> Category {
+        match self {
+            Action::MoveUp
+            | Action::MoveDown
+            | Action::NextOverdue
+            | Action::PreviousOverdue
+            | Action::NextPage
+            | Action::PreviousPage
+            | Action::GotoId => Category::Navigation,
+            Action::Add
+            | Action::AddMany
+            | Action::AddSubtask
+            | Action::ToggleFold
+            | Action::Toggle
+            | Action::CycleStatus
+            | Action::Delete
+            | Action::UndoDelete
+            | Action::ClearCompleted
+            | Action::OpenTags => Category::Todos,
+            Action::ToggleRecurrence
+            | Action::SetRecurUntil
+            | Action::SetDueDate
+            | Action::SetDueTime
+            | Action::Snooze
+            | Action::ToggleTimer
+            | Action::IncreaseProgress
+            | Action::DecreaseProgress
+            | Action::PriorityLow
+            | Action::PriorityMedium
+            | Action::PriorityHigh
+            | Action::ClearPriority => Category::Planning,
+            Action::ToggleCompact
+            | Action::ToggleStrikethrough
+            | Action::CycleFilter
+            | Action::CycleDueWindow
+            | Action::CollapseCompleted
+            | Action::OverdueView
+            | Action::Search
+            | Action::CycleSort
+            | Action::ReverseSort
+            | Action::OpenDetail
+            | Action::OpenLink
+            | Action::OpenArchive
+            | Action::OpenTrash
+            | Action::OpenLists
+            | Action::PreviousList => Category::View,
+            Action::Export
+            | Action::YankList
+            | Action::Reload
+            | Action::ReloadTheme
+            | Action::Help
+            | Action::CheatSheet
+            | Action::Quit => Category::General,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::NextOverdue => "Jump to the next overdue todo",
+            Action::PreviousOverdue => "Jump to the previous overdue todo",
+            Action::NextPage => "Show the next page of todos",
+            Action::PreviousPage => "Show the previous page of todos",
+            Action::GotoId => "Go to a todo by id",
+            Action::Add => "Add new todo",
+            Action::AddMany => "Add several todos in a row",
+            Action::AddSubtask => "Add a subtask to the selected todo",
+            Action::ToggleFold => "Fold/unfold subtasks",
+            Action::Toggle => "Toggle todo completion",
+            Action::CycleStatus => "Cycle status (open/in progress/done)",
+            Action::Delete => "Delete selected todo",
+            Action::UndoDelete => "Undo a delete (for a few seconds after)",
+            Action::ClearCompleted => "Clear completed todos",
+            Action::ToggleRecurrence => "Toggle daily recurrence",
+            Action::SetRecurUntil => "Set the last day a recurring todo repeats",
+            Action::SetDueDate => "Set a due date (ISO date, today, fri, +3d...)",
+            Action::SetDueTime => "Set a due time of day",
+            Action::Snooze => "Snooze (push due date forward)",
+            Action::ToggleTimer => "Start/pause time tracking",
+            Action::IncreaseProgress => "Increase progress by 10%",
+            Action::DecreaseProgress => "Decrease progress by 10%",
+            Action::PriorityLow => "Set priority low",
+            Action::PriorityMedium => "Set priority medium",
+            Action::PriorityHigh => "Set priority high",
+            Action::ClearPriority => "Clear priority",
+            Action::OpenTags => "Remove tags from selected todo",
+            Action::ToggleCompact => "Toggle compact display",
+            Action::ToggleStrikethrough => "Strike through completed todos or not",
+            Action::CycleFilter => "Cycle filter (all/active/completed)",
+            Action::CycleDueWindow => "Cycle due window (overdue/today/week/no date)",
+            Action::CollapseCompleted => "Collapse/expand completed todos",
+            Action::OverdueView => "Show only overdue todos, most overdue first",
+            Action::Search => "Search todos (#tag words, joined by AND or OR)",
+            Action::CycleSort => "Cycle sort mode",
+            Action::ReverseSort => "Reverse sort direction",
+            Action::OpenDetail => "View details and notes",
+            Action::OpenLink => "Open the first link in the todo",
+            Action::OpenArchive => "Browse and restore archived todos",
+            Action::OpenTrash => "Browse and restore deleted todos",
+            Action::OpenLists => "Switch, create and rename lists",
+            Action::PreviousList => "Go back to the list open before this one",
+            Action::Export => {
+                "Export the todos shown (filtered) to HTML, Markdown, CSV or todo.txt"
+            }
+            Action::YankList => "Copy the todos shown to the clipboard as Markdown",
+            Action::Reload => "Reload the list from disk",
+            Action::ReloadTheme => "Reload the theme from the config file",
+            Action::Help => "Show this help",
+            Action::CheatSheet => "Show a compact key reference",
+            Action::Quit => "Quit",
+        }
+    }
+}
+
+/// A key together with the modifiers that must be held, e.g. Ctrl-T. Shift
+/// is part of the character itself, so only Ctrl and Alt are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn ctrl(c: char) -> Self {
+        Self {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::CONTROL,
+        }
+    }
+
+    fn matches(self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let held = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        self.code == code && self.modifiers & held == modifiers & held
+    }
+}
+
+impl From<KeyCode> for Key {
+    fn from(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+}
+
+/// An action together with the keys bound to it.
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub action: Action,
+    pub keys: Vec<Key>,
+}
+
+/// The Normal mode key bindings. Key presses are dispatched and the help
+/// screens are generated from this table, so they cannot drift apart.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use KeyCode::{Char, Down, Enter, F, Left, Right, Tab, Up};
+
+        let keys =
+            |codes: &[KeyCode]| -> Vec<Key> { codes.iter().map(|&c| Key::from(c)).collect() };
+        let bindings = [
+            (Action::MoveUp, keys(&[Up, Char('k')])),
+            (Action::MoveDown, keys(&[Down, Char('j')])),
+            (Action::NextOverdue, keys(&[Char(']')])),
+            (Action::PreviousOverdue, keys(&[Char('[')])),
+            (Action::NextPage, keys(&[Right])),
+            (Action::PreviousPage, keys(&[Left])),
+            (Action::GotoId, keys(&[Char('g')])),
+            (Action::Add, keys(&[Char('i')])),
+            (Action::AddMany, keys(&[Char('I')])),
+            (Action::AddSubtask, keys(&[Char('a')])),
+            (Action::ToggleFold, keys(&[Char('Z')])),
+            (Action::Toggle, keys(&[Char(' '), Enter])),
+            (Action::CycleStatus, keys(&[Char('p')])),
+            (Action::Delete, keys(&[Char('d')])),
+            (Action::UndoDelete, keys(&[Char('z')])),
+            (Action::ClearCompleted, keys(&[Char('X')])),
+            (Action::OpenTags, keys(&[Char('T')])),
+            (Action::ToggleRecurrence, keys(&[Char('r')])),
+            (Action::SetRecurUntil, keys(&[Char('U')])),
+            (Action::SetDueDate, keys(&[Char('e')])),
+            (Action::SetDueTime, keys(&[Char('@')])),
+            (Action::Snooze, keys(&[Char('s')])),
+            (Action::ToggleTimer, keys(&[Char('t')])),
+            (Action::IncreaseProgress, keys(&[Char('+')])),
+            (Action::DecreaseProgress, keys(&[Char('-')])),
+            (Action::PriorityLow, keys(&[Char('1')])),
+            (Action::PriorityMedium, keys(&[Char('2')])),
+            (Action::PriorityHigh, keys(&[Char('3')])),
+            (Action::ClearPriority, keys(&[Char('0')])),
+            (Action::ToggleCompact, keys(&[Char('c')])),
+            (Action::ToggleStrikethrough, keys(&[Char('x')])),
+            (Action::CycleFilter, keys(&[Char('f')])),
+            (Action::CycleDueWindow, keys(&[Char('w')])),
+            (Action::CollapseCompleted, keys(&[Char('C')])),
+            (Action::OverdueView, keys(&[Char('!')])),
+            (Action::Search, keys(&[Char('/')])),
+            (Action::CycleSort, keys(&[Char('o')])),
+            (Action::ReverseSort, keys(&[Char('O')])),
+            (Action::OpenDetail, keys(&[Char('v')])),
+            (Action::OpenLink, keys(&[Char('l')])),
+            (Action::OpenArchive, keys(&[Char('A')])),
+            (Action::OpenTrash, keys(&[Char('D')])),
+            (Action::OpenLists, keys(&[Char('L')])),
+            (Action::PreviousList, keys(&[Tab])),
+            (Action::Export, keys(&[Char('E')])),
+            (Action::YankList, keys(&[Char('Y')])),
+            (Action::Reload, keys(&[Char('R')])),
+            (Action::ReloadTheme, vec![Key::ctrl('t')]),
+            (Action::Help, keys(&[Char('?')])),
+            (Action::CheatSheet, keys(&[F(1)])),
+            (Action::Quit, keys(&[Char('q')])),
+        ];
+
+        Self {
+            bindings: bindings
+                .into_iter()
+                .map(|(action, keys)| Binding { action, keys })
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// The action bound to `code` pressed with `modifiers`, if any.
+    pub fn action_for(&self, code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.keys.iter().any(|key| key.matches(code, modifiers)))
+            .map(|binding| binding.action)
+    }
+
+    /// Name of the first key bound to `action`, for short hints.
+    pub fn key_label(&self, action: Action) -> Option<String> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .and_then(|binding| binding.keys.first())
+            .map(|&key| key_name(key))
+    }
+
+    /// Display label for the keys bound to `action`.
+    pub fn keys_label(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .map(|binding| keys_label(&binding.keys))
+            .unwrap_or_default()
+    }
+}
+
+/// Human readable name of a key, as shown in the help screen.
+pub fn key_name(key: Key) -> String {
+    let name = code_name(key.code);
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        format!("Ctrl-{}", name.to_uppercase())
+    } else if key.modifiers.contains(KeyModifiers::ALT) {
+        format!("Alt-{}", name)
+    } else {
+        name
+    }
+}
+
+fn code_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    }
+}
+
+/// All keys bound to an action joined for display, e.g. `↑/k`.
+pub fn keys_label(keys: &[Key]) -> String {
+    keys.iter()
+        .map(|&key| key_name(key))
+        .collect::<Vec<_>>()
+        .join("/")
+}
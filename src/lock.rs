@@ -0,0 +1,89 @@
+use crate::logger;
+use color_eyre::Result;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Where the owner can't be checked, a lock this old is assumed to be left
+/// over from a crash.
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Marks a list file as open in this process, so a second instance doesn't
+/// overwrite its changes. The lock file is removed again when dropped.
+#[derive(Debug)]
+pub struct ListLock {
+    path: PathBuf,
+}
+
+/// Outcome of trying to lock a list.
+#[derive(Debug)]
+pub enum Acquired {
+    Locked(ListLock),
+    /// Another running instance has the list open.
+    HeldBy(u32),
+}
+
+impl ListLock {
+    /// The lock file for `data_file`, e.g. `todos.json.lock`.
+    pub fn path_for(data_file: &Path) -> PathBuf {
+        let mut name = data_file.file_name().unwrap_or_default().to_os_string();
+        name.push(".lock");
+        data_file.with_file_name(name)
+    }
+
+    /// Takes the lock for `data_file`, clearing it first if the instance that
+    /// held it is gone.
+    pub fn acquire(data_file: &Path) -> Result<Acquired> {
+        let path = Self::path_for(data_file);
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id())?;
+                    logger::debug!("locked {}", path.display());
+                    return Ok(Acquired::Locked(ListLock { path }));
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    let owner = fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|pid| pid.trim().parse::<u32>().ok());
+                    match owner {
+                        Some(pid) if pid == std::process::id() => {
+                            return Ok(Acquired::Locked(ListLock { path }));
+                        }
+                        Some(pid) if !is_stale(&path, pid) => return Ok(Acquired::HeldBy(pid)),
+                        _ => {
+                            logger::debug!("removing stale lock {}", path.display());
+                            fs::remove_file(&path)?;
+                        }
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Err(color_eyre::eyre::eyre!(
+            "could not lock {}, it keeps reappearing",
+            path.display()
+        ))
+    }
+}
+
+impl Drop for ListLock {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            logger::debug!("could not remove {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+/// Whether the lock at `path` held by `pid` was left behind.
+fn is_stale(path: &Path, pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        return !Path::new("/proc").join(pid.to_string()).exists();
+    }
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > STALE_AFTER)
+}
@@ -0,0 +1,34 @@
+use chrono::Local;
+use color_eyre::Result;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+static LOG_FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Starts appending debug messages to `path`. Logging stays disabled unless
+/// this is called, and never writes to stdout/stderr since that would
+/// corrupt the TUI.
+pub fn init(path: &str) -> Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let _ = LOG_FILE.set(Mutex::new(file));
+    Ok(())
+}
+
+pub fn write(message: &str) {
+    if let Some(file) = LOG_FILE.get()
+        && let Ok(mut file) = file.lock()
+    {
+        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
+        let _ = writeln!(file, "{} {}", timestamp, message);
+    }
+}
+
+/// Logs a formatted debug message when logging is enabled.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::logger::write(&format!($($arg)*))
+    };
+}
+
+pub(crate) use debug;
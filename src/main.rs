@@ -1,9 +1,23 @@
 mod app;
+mod backup;
+mod bidi;
+mod cli;
+mod config;
+mod editor;
 mod events;
+mod export;
+mod hooks;
+mod import;
+mod keymap;
+mod lock;
+mod logger;
+mod state;
 mod todo;
 mod ui;
+mod watch;
 
 use app::App;
+use cli::Cli;
 use color_eyre::Result;
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
@@ -19,45 +33,106 @@ fn main() -> Result<()> {
     // Initialize error handling
     color_eyre::install()?;
 
-    // Setup terminal
-    let mut terminal = setup_terminal()?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli = Cli::parse(&args).unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    });
+
+    if let Some(log_file) = &cli.log_file {
+        logger::init(log_file)?;
+        logger::debug!("oxitodo {} starting", env!("CARGO_PKG_VERSION"));
+    }
+
+    // Subcommands run without the TUI
+    if let Some(command) = cli.command {
+        if let Err(err) = cli::run(command, cli.list.as_deref()) {
+            logger::debug!("command failed: {}", err);
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
     // Create app and event handler
-    let mut app = App::new()?;
-    let event_handler = EventHandler::new();
+    let mut app = App::new(cli.list.as_deref(), true)?;
+
+    // Setup terminal
+    let mouse = app.config.mouse_enabled && !cli.no_mouse;
+    let (mut terminal, features) = setup_terminal(mouse)?;
+    let size = terminal.size()?;
+    app.handle_resize(size.width, size.height);
+    let mut event_handler = EventHandler::new(&app.config);
 
     // Run the main application loop
-    let result = run_app(&mut terminal, &mut app, &event_handler);
+    let result = run_app(&mut terminal, &mut app, &mut event_handler);
 
     // Restore terminal
-    restore_terminal(&mut terminal)?;
+    restore_terminal(&mut terminal, features)?;
 
     // Handle any errors that occurred during app execution
     if let Err(err) = result {
+        logger::debug!("application error: {}", err);
         eprintln!("Application error: {}", err);
         return Err(err);
     }
 
+    logger::debug!("exiting cleanly");
     println!("Thanks for using the todo app!");
     Ok(())
 }
 
-fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
-    enable_raw_mode()?;
+/// Terminal features that were successfully enabled during setup, so that
+/// restoring only undoes what was actually done.
+#[derive(Debug, Clone, Copy)]
+struct TerminalFeatures {
+    alternate_screen: bool,
+    mouse_capture: bool,
+}
+
+/// Puts the terminal in raw mode on the alternate screen, capturing the
+/// mouse unless `mouse` is off.
+fn setup_terminal(
+    mouse: bool,
+) -> Result<(Terminal<CrosstermBackend<io::Stdout>>, TerminalFeatures)> {
+    enable_raw_mode().map_err(|err| {
+        color_eyre::eyre::eyre!("Could not enable raw mode, is this a terminal? ({})", err)
+    })?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+
+    // Some restricted terminals lack an alternate screen; render inline there
+    let alternate_screen = execute!(stdout, EnterAlternateScreen).is_ok();
+    let mouse_capture = mouse && execute!(stdout, EnableMouseCapture).is_ok();
+
     let backend = CrosstermBackend::new(stdout);
-    let terminal = Terminal::new(backend)?;
-    Ok(terminal)
+    let mut terminal = Terminal::new(backend)?;
+    if !alternate_screen {
+        terminal.clear()?;
+    }
+
+    Ok((
+        terminal,
+        TerminalFeatures {
+            alternate_screen,
+            mouse_capture,
+        },
+    ))
 }
 
-fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+fn restore_terminal(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    features: TerminalFeatures,
+) -> Result<()> {
     disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    if features.mouse_capture {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
+    if features.alternate_screen {
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    } else {
+        // Inline rendering drew over the main screen, so wipe it on exit
+        terminal.clear()?;
+    }
     terminal.show_cursor()?;
     Ok(())
 }
@@ -65,7 +140,7 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Re
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
-    event_handler: &EventHandler,
+    event_handler: &mut EventHandler,
 ) -> Result<()> {
     loop {
         // Draw the UI
@@ -79,5 +154,5 @@ fn run_app(
             break;
         }
     }
-    Ok(())
+    app.flush()
 }
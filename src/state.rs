@@ -0,0 +1,56 @@
+use crate::app::{Filter, SortDirection, SortMode};
+use chrono::NaiveDate;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Application state persisted between runs, stored next to the todos file.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AppState {
+    /// Day the app was last started, from before lists tracked their own.
+    #[serde(default)]
+    pub last_opened: Option<NaiveDate>,
+    #[serde(default)]
+    pub search_history: Vec<String>,
+    /// Whether completed todos are struck through, once toggled in the app.
+    /// Unset until then, so the config applies.
+    #[serde(default)]
+    pub strikethrough: Option<bool>,
+    /// Per-list preferences, keyed by list name.
+    #[serde(default)]
+    pub lists: BTreeMap<String, ListView>,
+}
+
+/// How a list was last viewed, so each list keeps its own sort and filter.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct ListView {
+    #[serde(default)]
+    pub sort_mode: SortMode,
+    #[serde(default)]
+    pub sort_direction: SortDirection,
+    /// Unset until the filter is changed, so `hide_completed` applies.
+    #[serde(default)]
+    pub filter: Option<Filter>,
+    /// Day the list was last opened, for resetting recurring todos.
+    #[serde(default)]
+    pub last_opened: Option<NaiveDate>,
+}
+
+impl AppState {
+    pub fn load(file_path: &Path) -> Result<Self> {
+        if file_path.exists() {
+            let content = fs::read_to_string(file_path)?;
+            Ok(serde_json::from_str(&content)?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self, file_path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(file_path, json)?;
+        Ok(())
+    }
+}
@@ -1,10 +1,161 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Recurrence {
+    Daily,
+}
+
+/// Where a todo is in its life, from not started to done.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Done,
+}
+
+impl Status {
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Todo => "open",
+            Status::InProgress => "in progress",
+            Status::Done => "done",
+        }
+    }
+}
+
+/// Reads a status, or the `completed` flag files had before statuses.
+fn deserialize_status<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Status, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StatusOrCompleted {
+        Status(Status),
+        Completed(bool),
+    }
+
+    Ok(match StatusOrCompleted::deserialize(deserializer)? {
+        StatusOrCompleted::Status(status) => status,
+        StatusOrCompleted::Completed(true) => Status::Done,
+        StatusOrCompleted::Completed(false) => Status::Todo,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Priority {
+    #[serde(alias = "low")]
+    Low,
+    #[serde(alias = "medium")]
+    Medium,
+    #[serde(alias = "high")]
+    High,
+}
+
+impl Priority {
+    /// Reads a priority as typed after `!` in quick capture, e.g. `high`,
+    /// `med` or `3`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "low" | "l" | "1" => Some(Priority::Low),
+            "medium" | "med" | "m" | "2" => Some(Priority::Medium),
+            "high" | "h" | "3" => Some(Priority::High),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    }
+}
+
+/// Where a todo was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Source {
+    #[serde(alias = "tui")]
+    Tui,
+    #[serde(alias = "cli")]
+    Cli,
+    #[serde(alias = "import")]
+    Import,
+}
+
+impl Source {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "tui" => Some(Source::Tui),
+            "cli" => Some(Source::Cli),
+            "import" => Some(Source::Import),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Tui => "tui",
+            Source::Cli => "cli",
+            Source::Import => "import",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TodoItem {
     pub id: usize,
     pub text: String,
-    pub completed: bool,
+    #[serde(default, alias = "completed", deserialize_with = "deserialize_status")]
+    pub status: Status,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(default)]
+    pub due: Option<NaiveDate>,
+    /// Time of day the todo is due, for appointments. Only used together
+    /// with `due`.
+    #[serde(default)]
+    pub due_time: Option<NaiveTime>,
+    /// When the todo was added. Todos from before this was recorded have
+    /// none.
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub accumulated_seconds: u64,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Project or place the todo belongs to, with its sigil, e.g.
+    /// `+projectX` or `@home`. Unlike tags there is at most one.
+    #[serde(default)]
+    pub context: Option<String>,
+    /// Last day a recurring todo comes back; it stops recurring after this.
+    #[serde(default)]
+    pub recur_until: Option<NaiveDate>,
+    /// How far along the todo is, in percent.
+    #[serde(default)]
+    pub progress: Option<u8>,
+    /// Id of the todo this is a subtask of.
+    #[serde(default)]
+    pub parent: Option<usize>,
+    /// Whether this todo's subtasks are hidden.
+    #[serde(default)]
+    pub folded: bool,
+    /// A line jotted down when the todo was completed.
+    #[serde(default)]
+    pub completion_note: Option<String>,
+    /// Where the todo was added. Todos from before this was recorded have
+    /// none.
+    #[serde(default)]
+    pub source: Option<Source>,
 }
 
 impl TodoItem {
@@ -12,15 +163,163 @@ impl TodoItem {
         Self {
             id,
             text,
-            completed: false,
+            status: Status::Todo,
+            recurrence: None,
+            due: None,
+            due_time: None,
+            created_at: Some(Utc::now()),
+            completed_at: None,
+            started_at: None,
+            accumulated_seconds: 0,
+            notes: String::new(),
+            priority: None,
+            tags: Vec::new(),
+            context: None,
+            recur_until: None,
+            progress: None,
+            parent: None,
+            folded: false,
+            completion_note: None,
+            source: None,
+        }
+    }
+
+    /// Creates a todo from quick-capture input, where `#word` tokens become
+    /// tags, `!high` style tokens set the priority and a `+project` or
+    /// `@place` token sets the context. Input made up only of those is kept
+    /// as plain text.
+    pub fn from_input(id: usize, input: &str) -> Self {
+        let mut words = Vec::new();
+        let mut tags: Vec<String> = Vec::new();
+        let mut priority = None;
+        let mut context = None;
+        for word in input.split_whitespace() {
+            if let Some(parsed) = word.strip_prefix('!').and_then(Priority::parse) {
+                priority = Some(parsed);
+                continue;
+            }
+            if is_context(word) {
+                context = Some(word.to_string());
+                continue;
+            }
+            match word.strip_prefix('#') {
+                Some(tag) if !tag.is_empty() => {
+                    if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                        tags.push(tag.to_string());
+                    }
+                }
+                _ => words.push(word),
+            }
+        }
+
+        if words.is_empty() {
+            return Self::new(id, input.trim().to_string());
         }
+
+        let mut todo = Self::new(id, words.join(" "));
+        todo.tags = tags;
+        todo.priority = priority;
+        todo.context = context;
+        todo
     }
 
+    /// Marks an open or in-progress todo done, or reopens a done one.
     pub fn toggle_completion(&mut self) {
-        self.completed = !self.completed;
+        self.status = if self.is_completed() {
+            Status::Todo
+        } else {
+            Status::Done
+        };
+        self.completed_at = self.is_completed().then(Utc::now);
+        if !self.is_completed() {
+            self.completion_note = None;
+        }
     }
 
     pub fn is_completed(&self) -> bool {
-        self.completed
+        self.status == Status::Done
+    }
+
+    pub fn is_in_progress(&self) -> bool {
+        self.status == Status::InProgress
+    }
+
+    /// The tag a todo is grouped under when sorting by tag: its first one.
+    pub fn primary_tag(&self) -> Option<&str> {
+        self.tags.first().map(String::as_str)
+    }
+
+    pub fn is_recurring(&self) -> bool {
+        self.recurrence.is_some()
+    }
+
+    /// Whether a recurring todo should come back on `day`. The end date
+    /// itself still gets an occurrence.
+    pub fn recurs_on(&self, day: NaiveDate) -> bool {
+        self.is_recurring() && self.recur_until.is_none_or(|until| day <= until)
+    }
+
+    /// The first `http://` or `https://` link in the text, without any
+    /// punctuation that ends the sentence around it.
+    pub fn first_url(&self) -> Option<&str> {
+        self.text.split_whitespace().find_map(|word| {
+            let start = word.find("https://").or_else(|| word.find("http://"))?;
+            Some(word[start..].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'', '"']))
+        })
+    }
+
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.is_completed() && self.due.is_some_and(|due| due < today)
+    }
+
+    /// The moment a todo with both a due date and a due time falls due.
+    pub fn due_at(&self) -> Option<NaiveDateTime> {
+        Some(self.due?.and_time(self.due_time?))
     }
+
+    pub fn is_tracking(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    pub fn start_timer(&mut self, now: DateTime<Utc>) {
+        if self.started_at.is_none() {
+            self.started_at = Some(now);
+        }
+    }
+
+    pub fn stop_timer(&mut self, now: DateTime<Utc>) {
+        if let Some(started_at) = self.started_at.take() {
+            self.accumulated_seconds += (now - started_at).num_seconds().max(0) as u64;
+        }
+    }
+
+    /// Total tracked time, including the currently running session.
+    pub fn tracked_seconds(&self, now: DateTime<Utc>) -> u64 {
+        let running = self.started_at.map_or(0, |started_at| {
+            (now - started_at).num_seconds().max(0) as u64
+        });
+        self.accumulated_seconds + running
+    }
+
+    pub fn toggle_daily_recurrence(&mut self) {
+        self.recurrence = match self.recurrence {
+            Some(Recurrence::Daily) => None,
+            None => Some(Recurrence::Daily),
+        };
+    }
+}
+
+/// Whether `word` names a context: `+` or `@` followed by a letter, so
+/// `+1` or `@5pm` stays plain text.
+pub fn is_context(word: &str) -> bool {
+    word.strip_prefix(['+', '@'])
+        .and_then(|name| name.chars().next())
+        .is_some_and(char::is_alphabetic)
+}
+
+/// A todo in the recycle bin, along with when it was deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedTodo {
+    pub todo: TodoItem,
+    pub deleted_at: DateTime<Utc>,
 }
@@ -1,19 +1,93 @@
-use crate::app::{App, AppMode};
+use crate::app::{App, AppMode, DEFAULT_LIST_NAME, DueWindow, Filter, SortDirection, SortMode};
+use crate::bidi;
+use crate::config::StatusOverflow;
+use crate::keymap::{Action, Category, keys_label};
+use crate::todo::{Priority, Status, TodoItem};
+use chrono::{Local, Utc};
 use ratatui::{
     Frame,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Margin},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Wrap,
+    },
 };
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::Instant;
+use unicode_width::UnicodeWidthChar;
 
 pub fn render_todos(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
-    let items: Vec<ListItem> = app
-        .todos
+    let today = Local::now().date_naive();
+    let now = Utc::now();
+    let visible_rows = app.visible_rows();
+    let visible: Vec<usize> = visible_rows.iter().map(|&(i, _)| i).collect();
+    // Without borders the scrollbar needs a column of its own
+    let grouped = app.config.group_by_tag && app.sort_mode() == SortMode::ByTag;
+    let scrolls = list_rows(app, &visible, grouped) > app.list_height();
+    let borders = if app.dense { scrolls as usize } else { 2 };
+    let row_width = (area.width as usize)
+        .saturating_sub(borders)
+        .saturating_sub(Span::raw(app.config.theme.highlight_symbol.as_str()).width());
+    let parents = app.parent_ids();
+    let strikethrough = app.strikethrough();
+    // Todos completed just now, with how far their strikethrough has swept
+    let frame_time = Instant::now();
+    let sweeps: HashMap<usize, f64> = visible
         .iter()
-        .map(|todo| {
-            let status = if todo.completed { "✓" } else { " " };
-            let style = if todo.completed {
+        .filter_map(|&i| {
+            let id = app.todos[i].id;
+            app.completion_sweep(id, frame_time)
+                .map(|progress| (id, progress))
+        })
+        .collect();
+
+    // Size the metadata columns from the rows on screen so they line up
+    let format_due = |todo: &TodoItem| {
+        due_label(todo, &app.config.date_format).map(|label| format!("due {}", label))
+    };
+    let show_priority = visible.iter().any(|&i| app.todos[i].priority.is_some());
+    let show_progress = visible.iter().any(|&i| app.todos[i].progress.is_some());
+    let id_width = if app.config.show_ids {
+        visible
+            .iter()
+            .map(|&i| app.todos[i].id.to_string().len())
+            .max()
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    let due_width = visible
+        .iter()
+        .filter_map(|&i| format_due(&app.todos[i]))
+        .map(|label| Span::raw(label).width())
+        .max()
+        .unwrap_or(0);
+
+    let rows: Vec<ListItem> = visible_rows
+        .iter()
+        .map(|&(i, depth)| {
+            let todo = &app.todos[i];
+            let theme = &app.config.theme;
+            let (status, status_style) = match todo.status {
+                Status::Todo => (
+                    theme.pending_symbol.as_str(),
+                    Style::default().fg(Color::White),
+                ),
+                Status::InProgress => ("~", Style::default().fg(Color::Yellow)),
+                // Without strikethrough the symbol alone marks the todo done
+                Status::Done if !strikethrough => (
+                    theme.completed_symbol.as_str(),
+                    Style::default().fg(Color::Green),
+                ),
+                Status::Done => (
+                    theme.completed_symbol.as_str(),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            };
+            let style = if todo.is_completed() && strikethrough {
                 Style::default()
                     .fg(Color::DarkGray)
                     .add_modifier(Modifier::CROSSED_OUT)
@@ -21,94 +95,1069 @@ pub fn render_todos(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
                 Style::default().fg(Color::White)
             };
 
-            ListItem::new(Line::from(vec![
-                Span::styled(format!("[{}] ", status), style),
-                Span::styled(&todo.text, style),
-            ]))
+            let mut spans = Vec::new();
+            if depth > 0 {
+                spans.push(Span::raw("  ".repeat(depth)));
+            }
+            if parents.contains(&todo.id) {
+                let marker = if todo.folded { "▸ " } else { "▾ " };
+                spans.push(Span::styled(marker, Style::default().fg(Color::DarkGray)));
+            }
+            spans.push(Span::styled(format!("[{}] ", status), status_style));
+            if id_width > 0 {
+                spans.push(Span::styled(
+                    format!("{:>id_width$} ", todo.id),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            if todo.is_recurring() {
+                spans.push(Span::styled("↻ ", Style::default().fg(Color::Magenta)));
+            }
+            let text = if app.config.bidi {
+                bidi::display(&todo.text)
+            } else {
+                todo.text.as_str().into()
+            };
+            match sweeps.get(&todo.id) {
+                Some(&progress) => spans.extend(sweep(text, progress, style)),
+                None => spans.push(Span::styled(text, style)),
+            }
+            if let Some(context) = &todo.context {
+                spans.push(Span::styled(
+                    format!(" {}", context),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            for tag in &todo.tags {
+                spans.push(Span::styled(
+                    format!(" #{}", tag),
+                    Style::default().fg(Color::Cyan),
+                ));
+            }
+
+            // Metadata is right-aligned so priority and due line up across rows
+            let mut meta = Vec::new();
+            if show_progress {
+                meta.push(match todo.progress {
+                    Some(progress) => progress_span(progress),
+                    None => Span::raw(" ".repeat(PROGRESS_WIDTH)),
+                });
+            }
+            if show_priority {
+                meta.push(match todo.priority {
+                    Some(priority) => priority_span(priority),
+                    None => Span::raw(" ".repeat(PRIORITY_WIDTH)),
+                });
+            }
+            if due_width > 0 {
+                let text = format_due(todo).unwrap_or_default();
+                let due_style = match todo.due {
+                    _ if todo.is_overdue(today) => Style::default().fg(Color::Red),
+                    Some(due) if due == today && !todo.is_completed() => {
+                        Style::default().fg(Color::Yellow)
+                    }
+                    _ => Style::default().fg(Color::DarkGray),
+                };
+                meta.push(Span::styled(format!(" {:>due_width$}", text), due_style));
+            }
+            let tracked = todo.tracked_seconds(now);
+            if tracked > 0 {
+                let timer_style = if todo.is_tracking() {
+                    Style::default().fg(Color::Green)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                meta.push(Span::styled(
+                    format!(" [{}]", format_duration(tracked)),
+                    timer_style,
+                ));
+            }
+
+            ListItem::new(columns(spans, meta, row_width))
         })
         .collect();
 
-    let title = format!(" Todos ({}) ", app.todos.len());
+    let mut title = format!(" {} ({}) ", list_title(app), rows.len());
+    if app.config.paged {
+        let (page, pages) = app.page();
+        if pages > 1 {
+            title.push_str(&format!("Page {}/{} ", page + 1, pages));
+        }
+        // Each page starts at a multiple of the page size rather than
+        // scrolling one row at a time
+        *app.list_state.offset_mut() = page * app.list_height().max(1);
+    }
+
+    // Headers take up rows of their own, so the selection is moved down
+    // past the ones above it
+    let mut items = Vec::new();
+    let mut positions = Vec::new();
+    let mut group = None;
+    for (&i, row) in visible.iter().zip(rows) {
+        let tag = app.todos[i].primary_tag().map(str::to_lowercase);
+        if grouped && (items.is_empty() || group != Some(tag.clone())) {
+            let header = match &tag {
+                Some(tag) => format!("#{}", tag),
+                None => "untagged".to_string(),
+            };
+            items.push(ListItem::new(Line::from(Span::styled(
+                header,
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ))));
+            group = Some(tag);
+        }
+        positions.push(items.len());
+        items.push(row);
+    }
+
+    // Sits after the last todo, so the selection never lands on it
+    let collapsed = app.collapsed_count();
+    if collapsed > 0 {
+        items.push(ListItem::new(Line::from(Span::styled(
+            format!(
+                "▸ {} completed (hidden, {} to expand)",
+                collapsed,
+                app.keymap.keys_label(Action::CollapseCompleted)
+            ),
+            Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+        ))));
+    }
+    let celebrate = app.config.celebrate_all_done && app.all_done();
+    let rows_used = items.len();
+    let inner = panel_block(app.dense, "", Color::Blue).inner(area);
     let list = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(title)
-                .border_style(Style::default().fg(Color::Blue)),
-        )
+        .block(panel_block(app.dense, title, Color::Blue))
         .highlight_style(
             Style::default()
                 .bg(Color::DarkGray)
                 .add_modifier(Modifier::BOLD),
         )
-        .highlight_symbol("> ");
+        .highlight_symbol(app.config.theme.highlight_symbol.as_str());
+
+    if grouped {
+        let mut state = ListState::default()
+            .with_offset(app.list_state.offset())
+            .with_selected(
+                app.list_state
+                    .selected()
+                    .and_then(|s| positions.get(s).copied()),
+            );
+        f.render_stateful_widget(list, area, &mut state);
+        *app.list_state.offset_mut() = state.offset();
+    } else {
+        f.render_stateful_widget(list, area, &mut app.list_state);
+    }
+
+    if rows_used > inner.height as usize {
+        // Positions run over the offsets, so the thumb ends at the bottom
+        let mut scroll = ScrollbarState::new(rows_used - inner.height as usize + 1)
+            .viewport_content_length(inner.height as usize)
+            .position(app.list_state.offset());
+        // Drawn over the right border, or in the column kept free for it
+        let track = if app.dense {
+            area
+        } else {
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            })
+        };
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .style(Style::default().fg(Color::Blue));
+        f.render_stateful_widget(scrollbar, track, &mut scroll);
+    }
+
+    if celebrate {
+        render_all_done(f, inner, rows_used);
+    }
+}
+
+/// Rows the todo list takes: the todos, a header per tag group and the
+/// line for hidden completed todos.
+fn list_rows(app: &App, visible: &[usize], grouped: bool) -> usize {
+    let mut headers = 0;
+    if grouped {
+        let mut group = None;
+        for &i in visible {
+            let tag = app.todos[i].primary_tag().map(str::to_lowercase);
+            if headers == 0 || group.as_ref() != Some(&tag) {
+                headers += 1;
+                group = Some(tag);
+            }
+        }
+    }
+    visible.len() + headers + (app.collapsed_count() > 0) as usize
+}
+
+/// Lines of the banner shown once every todo is done.
+const ALL_DONE_BANNER: [&str; 3] = ["\\o/", "All done! 🎉", "Nothing left to do"];
+
+/// Draws the all-done banner centred in the rows below the last of
+/// `rows_used`, if it fits there.
+fn render_all_done(f: &mut Frame, inner: ratatui::layout::Rect, rows_used: usize) {
+    let free = (inner.height as usize).saturating_sub(rows_used);
+    // A blank row keeps it apart from the list
+    let needed = ALL_DONE_BANNER.len() + 1;
+    if free < needed {
+        return;
+    }
+    let top = inner.y + (rows_used + 1 + (free - needed) / 2) as u16;
+    let banner_area =
+        ratatui::layout::Rect::new(inner.x, top, inner.width, ALL_DONE_BANNER.len() as u16);
+    let lines: Vec<Line> = ALL_DONE_BANNER
+        .iter()
+        .map(|line| Line::from(*line))
+        .collect();
+    let banner = Paragraph::new(lines).alignment(Alignment::Center).style(
+        Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD),
+    );
+    f.render_widget(banner, banner_area);
+}
 
-    f.render_stateful_widget(list, area, &mut app.list_state);
+/// Completed `text` partway through its strikethrough animation: `done`
+/// style over the first `progress` of it and plain text after.
+fn sweep(text: Cow<'_, str>, progress: f64, done: Style) -> Vec<Span<'_>> {
+    let width = Span::raw(text.as_ref()).width();
+    let struck = (width as f64 * progress).ceil() as usize;
+    let mut split = text.len();
+    let mut columns = 0;
+    for (i, c) in text.char_indices() {
+        if columns >= struck {
+            split = i;
+            break;
+        }
+        columns += c.width().unwrap_or(0);
+    }
+    let (head, tail) = text.split_at(split);
+    vec![
+        Span::styled(head.to_string(), done),
+        Span::styled(tail.to_string(), Style::default().fg(Color::White)),
+    ]
+}
+
+/// Lays out a row with `left` flush left and `right` flush right within
+/// `width` columns. When both don't fit, the left side is truncated with an
+/// ellipsis so the right-hand columns keep their position. Trailing blank
+/// cells in `right` only hold the alignment and may be covered by text.
+fn columns<'a>(left: Vec<Span<'a>>, mut right: Vec<Span<'a>>, width: usize) -> Line<'a> {
+    let right_width: usize = right.iter().map(Span::width).sum();
+    while right
+        .last()
+        .is_some_and(|span| span.content.trim().is_empty())
+    {
+        right.pop();
+    }
+    let shown_width: usize = right.iter().map(Span::width).sum();
+    if shown_width == 0 {
+        return Line::from(truncate_spans(left, width));
+    }
+    let end = width.saturating_sub(right_width - shown_width);
+
+    // Keep at least one space between the text and its metadata
+    let left = truncate_spans(left, end.saturating_sub(shown_width + 1));
+    let left_width: usize = left.iter().map(Span::width).sum();
+    let padding = end.saturating_sub(left_width + shown_width).max(1);
+
+    let mut spans = left;
+    spans.push(Span::raw(" ".repeat(padding)));
+    spans.extend(right);
+    Line::from(spans)
+}
+
+/// Cuts `spans` down to at most `max` display columns, ending in `…` when
+/// anything was dropped.
+fn truncate_spans(spans: Vec<Span<'_>>, max: usize) -> Vec<Span<'_>> {
+    let total: usize = spans.iter().map(Span::width).sum();
+    if total <= max {
+        return spans;
+    }
+
+    let mut budget = max.saturating_sub(1);
+    let mut out = Vec::new();
+    let mut last_style = Style::default();
+    for span in spans {
+        last_style = span.style;
+        if budget == 0 {
+            break;
+        }
+        let width = span.width();
+        if width <= budget {
+            budget -= width;
+            out.push(span);
+            continue;
+        }
+        let mut cut = String::new();
+        for c in span.content.chars() {
+            let w = UnicodeWidthChar::width(c).unwrap_or(0);
+            if w > budget {
+                break;
+            }
+            budget -= w;
+            cut.push(c);
+        }
+        out.push(Span::styled(cut, span.style));
+        break;
+    }
+    if max > 0 {
+        out.push(Span::styled("…", last_style));
+    }
+    out
 }
 
 pub fn render_input(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
-    let input = Paragraph::new(app.input.value()).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(" New Todo ")
-            .border_style(Style::default().fg(Color::Green)),
+    let (input, title, color) = match app.mode {
+        AppMode::Search => (&app.search, " Search ", Color::Yellow),
+        AppMode::Prompt => (
+            &app.input,
+            app.prompt.map_or(" Input ", |p| p.title()),
+            Color::Magenta,
+        ),
+        _ if app.new_parent.is_some() => {
+            (&app.input, " New Subtask (Esc to finish) ", Color::Green)
+        }
+        _ if app.rapid_entry => (
+            &app.input,
+            " New Todos (empty line or Esc to finish) ",
+            Color::Green,
+        ),
+        _ => (&app.input, " New Todo ", Color::Green),
+    };
+
+    // Only new todos get a prefix; prompts and search say what they are in
+    // the title
+    let prefix = match app.mode {
+        AppMode::Insert => app.config.theme.input_prefix.as_str(),
+        _ => "",
+    };
+    let block = panel_block(app.dense, title, color);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let [prefix_area, text_area] = Layout::horizontal([
+        Constraint::Length(Span::raw(prefix).width() as u16),
+        Constraint::Min(0),
+    ])
+    .areas(inner);
+    f.render_widget(
+        Span::styled(prefix, Style::default().fg(Color::DarkGray)),
+        prefix_area,
     );
 
-    f.render_widget(input, area);
+    // Scroll sideways so the cursor stays visible in long input, keeping
+    // one column free for it at the end
+    let width = text_area.width.saturating_sub(1) as usize;
+    let (text_area, cursor, scroll) =
+        if app.config.bidi && input.value().chars().any(bidi::is_rtl_char) {
+            layout_bidi_input(input, text_area)
+        } else {
+            let scroll = input.visual_scroll(width);
+            (text_area, input.visual_cursor(), scroll)
+        };
+    let text = if app.config.bidi {
+        bidi::display(input.value())
+    } else {
+        input.value().into()
+    };
+    f.render_widget(Paragraph::new(text).scroll((0, scroll as u16)), text_area);
+
+    if let (AppMode::Prompt, Some(error)) = (&app.mode, &app.prompt_error) {
+        f.render_widget(
+            Paragraph::new(Span::styled(
+                error.as_str(),
+                Style::default().fg(Color::Red),
+            ))
+            .alignment(Alignment::Right),
+            text_area,
+        );
+    }
 
-    // Set cursor position
-    f.set_cursor_position((area.x + app.input.visual_cursor() as u16 + 1, area.y + 1));
+    f.set_cursor_position((text_area.x + (cursor - scroll) as u16, text_area.y));
 }
 
-pub fn render_help(f: &mut Frame, area: ratatui::layout::Rect) {
-    let help_text = vec![
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Navigation:",
+/// Places input containing right-to-left text: the area to draw it in, the
+/// cursor column and the columns scrolled past. Right-to-left input is
+/// aligned right and ends on the left, so a column is kept free there for
+/// the cursor when it is at the end.
+fn layout_bidi_input(
+    input: &tui_input::Input,
+    area: ratatui::layout::Rect,
+) -> (ratatui::layout::Rect, usize, usize) {
+    let line = bidi::layout(input.value());
+    let pad = usize::from(line.rtl);
+    let total = line.width + 1;
+    let cursor = match line.columns.get(input.cursor()) {
+        Some(&column) => column + pad,
+        None if line.rtl => 0,
+        None => line.width,
+    };
+
+    let width = area.width as usize;
+    if total <= width {
+        let shift = if line.rtl { (width - total) as u16 } else { 0 };
+        let area = ratatui::layout::Rect {
+            x: area.x + shift,
+            width: area.width - shift,
+            ..area
+        };
+        return (area, cursor, 0);
+    }
+    let scroll = if line.rtl {
+        // Show the start of the text, which is at the right end
+        (total - width).min(cursor)
+    } else {
+        cursor.saturating_sub(width.saturating_sub(1))
+    };
+    (area, cursor, scroll)
+}
+
+/// Keys of the text entry modes, which are fixed rather than remappable.
+const HELP_TEXT_MODES: [(&str, &[(&str, &str)]); 2] = [
+    (
+        "Insert Mode",
+        &[
+            ("Enter", "Add todo and return to normal mode"),
+            ("#word", "Tag the todo with 'word'"),
+            ("!high", "Set the priority (low, medium, high)"),
+            ("+project", "Set the context"),
+            ("Esc", "Cancel and return to normal mode"),
+        ],
+    ),
+    (
+        "Search Mode",
+        &[
+            ("Enter", "Keep the search applied"),
+            ("Esc", "Clear the search"),
+            ("↑/↓", "Recall previous searches"),
+            (
+                "source:cli",
+                "Only todos added from the tui, cli or an import",
+            ),
+            ("+project", "Only todos with this context (or @place)"),
+        ],
+    ),
+];
+
+pub fn render_help(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
+    let heading = |title: String| {
+        Line::from(Span::styled(
+            title,
             Style::default()
                 .fg(Color::Yellow)
                 .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  ↑/k    - Move up"),
-        Line::from("  ↓/j    - Move down"),
+        ))
+    };
+
+    // Normal mode sections come straight from the keymap, followed by the
+    // fixed keys of the text entry modes
+    let mut sections: Vec<(String, Vec<(String, &str)>)> = Category::ALL
+        .into_iter()
+        .map(|category| {
+            let entries = app
+                .keymap
+                .bindings()
+                .iter()
+                .filter(|binding| binding.action.category() == category)
+                .map(|binding| (keys_label(&binding.keys), binding.action.description()))
+                .collect();
+            (category.title().to_string(), entries)
+        })
+        .collect();
+    for (title, entries) in HELP_TEXT_MODES {
+        let entries = entries
+            .iter()
+            .map(|&(keys, description)| (keys.to_string(), description))
+            .collect();
+        sections.push((title.to_string(), entries));
+    }
+
+    let key_width = sections
+        .iter()
+        .flat_map(|(_, entries)| entries)
+        .map(|(keys, _)| Span::raw(keys.as_str()).width())
+        .max()
+        .unwrap_or(0);
+    let popup_area = centered_rect_clamped(60, 80, HELP_MIN_SIZE, HELP_MAX_SIZE, area);
+    // Wrapped descriptions continue under the description column
+    let indent = 2 + key_width + 3;
+    let description_width = (popup_area.width as usize)
+        .saturating_sub(2 + indent)
+        .max(10);
+
+    let mut help_text = vec![Line::from("")];
+    for (title, entries) in sections.iter().filter(|(_, e)| !e.is_empty()) {
+        help_text.push(heading(format!("{}:", title)));
+        for (keys, description) in entries {
+            let pad = key_width.saturating_sub(Span::raw(keys.as_str()).width());
+            for (i, chunk) in wrap_words(description, description_width)
+                .into_iter()
+                .enumerate()
+            {
+                help_text.push(Line::from(if i == 0 {
+                    format!("  {}{} - {}", keys, " ".repeat(pad), chunk)
+                } else {
+                    format!("{}{}", " ".repeat(indent), chunk)
+                }));
+            }
+        }
+        help_text.push(Line::from(""));
+    }
+    help_text.push(Line::from(format!(
+        "Press a for version info, {} or Esc to close this help",
+        app.keymap.keys_label(Action::Help)
+    )));
+
+    if app.dense {
+        // Drop the blank spacer lines so the popup fits on small screens
+        help_text.retain(|line| line.width() > 0);
+    }
+
+    // Stop scrolling once the last line is in view
+    let max_scroll = (help_text.len() as u16).saturating_sub(popup_area.height.saturating_sub(2));
+    app.help_scroll = app.help_scroll.min(max_scroll);
+
+    let paragraph = Paragraph::new(help_text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Help ")
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .wrap(Wrap { trim: false })
+        .scroll((app.help_scroll, 0))
+        .alignment(Alignment::Left);
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+/// Every binding in two columns, filled top to bottom then left to right.
+pub fn render_cheat_sheet(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
+    let entries: Vec<(String, &'static str)> = app
+        .keymap
+        .bindings()
+        .iter()
+        .map(|binding| (keys_label(&binding.keys), binding.action.description()))
+        .collect();
+    let key_width = entries
+        .iter()
+        .map(|(keys, _)| Span::raw(keys.as_str()).width())
+        .max()
+        .unwrap_or(0);
+
+    let popup_area =
+        centered_rect_clamped(90, 80, CHEAT_SHEET_MIN_SIZE, CHEAT_SHEET_MAX_SIZE, area);
+    let gap = 2;
+    let cell_width = (popup_area.width as usize).saturating_sub(2 + gap) / 2;
+    let cell = |(keys, description): &(String, &'static str)| {
+        let pad = key_width.saturating_sub(Span::raw(keys.as_str()).width());
+        let spans = truncate_spans(
+            vec![
+                Span::styled(
+                    format!("{}{} ", keys, " ".repeat(pad)),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::raw(*description),
+            ],
+            cell_width,
+        );
+        let width: usize = spans.iter().map(Span::width).sum();
+        (spans, width)
+    };
+
+    let rows = entries.len().div_ceil(2);
+    let lines: Vec<Line> = (0..rows)
+        .map(|row| {
+            let (mut spans, width) = cell(&entries[row]);
+            if let Some(right) = entries.get(row + rows) {
+                spans.push(Span::raw(" ".repeat(cell_width - width + gap)));
+                spans.extend(cell(right).0);
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    let max_scroll = (lines.len() as u16).saturating_sub(popup_area.height.saturating_sub(2));
+    app.help_scroll = app.help_scroll.min(max_scroll);
+
+    let title = format!(
+        " Keys ({} or Esc to close) ",
+        app.keymap.keys_label(Action::CheatSheet)
+    );
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .scroll((app.help_scroll, 0));
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+pub fn render_about(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let label = Style::default().fg(Color::Yellow);
+    let text = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            env!("CARGO_PKG_NAME"),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Version:   ", label),
+            Span::raw(env!("CARGO_PKG_VERSION")),
+        ]),
+        Line::from(vec![
+            Span::styled("Data file: ", label),
+            Span::raw(app.data_file()),
+        ]),
+        Line::from(""),
+        Line::from("Press Esc to go back"),
+    ];
+
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" About ")
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .wrap(Wrap { trim: false });
+
+    let popup_area = centered_rect(60, 40, area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+pub fn render_confirm(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let message = app.confirm_message().unwrap_or_default();
+
+    let paragraph = Paragraph::new(vec![Line::from(""), Line::from(message)])
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Confirm ")
+                .border_style(Style::default().fg(Color::Red)),
+        )
+        .wrap(Wrap { trim: true })
+        .alignment(Alignment::Center);
+
+    let popup_area = centered_rect_clamped(50, 20, CONFIRM_MIN_SIZE, CONFIRM_MAX_SIZE, area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+pub fn render_dashboard(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let today = Local::now().date_naive();
+    let summary = app.day_summary(today);
+
+    let count_line = |label: &'static str, count: usize, color: Color| {
+        Line::from(vec![
+            Span::raw(format!("  {:<16}", label)),
+            Span::styled(
+                count.to_string(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+        ])
+    };
+
+    let text = vec![
         Line::from(""),
-        Line::from(vec![Span::styled(
-            "Actions:",
+        Line::from(Span::styled(
+            today.format("%A, %B %-d").to_string(),
             Style::default()
                 .fg(Color::Yellow)
                 .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  i      - Add new todo"),
-        Line::from("  Space  - Toggle todo completion"),
-        Line::from("  d      - Delete selected todo"),
-        Line::from("  ?      - Show this help"),
-        Line::from("  q      - Quit"),
+        )),
+        Line::from(""),
+        count_line("Overdue", summary.overdue, Color::Red),
+        count_line("Due today", summary.due_today, Color::Yellow),
+        count_line("Completed today", summary.completed_today, Color::Green),
+        count_line("Active", summary.active, Color::White),
         Line::from(""),
-        Line::from(vec![Span::styled(
-            "Insert Mode:",
+        Line::from("Press any key to continue"),
+    ];
+
+    let paragraph = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Today ")
+            .border_style(Style::default().fg(Color::Magenta)),
+    );
+
+    let popup_area = centered_rect(50, 50, area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+pub fn render_archive(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
+    let items: Vec<ListItem> = app
+        .archive
+        .iter()
+        .map(|todo| {
+            let completed = todo
+                .completed_at
+                .map(|at| {
+                    let date = at.with_timezone(&Local).date_naive();
+                    format!(" (done {})", date.format(&app.config.date_format))
+                })
+                .unwrap_or_default();
+            let mut spans = vec![
+                Span::raw(todo.text.as_str()),
+                Span::styled(completed, Style::default().fg(Color::DarkGray)),
+            ];
+            if let Some(note) = &todo.completion_note {
+                spans.push(Span::styled(
+                    format!(" — {}", note),
+                    Style::default().fg(Color::Cyan),
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let title = format!(" Archive ({}) ", items.len());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(Color::Magenta));
+
+    let popup_area = centered_rect(70, 70, area);
+    f.render_widget(Clear, popup_area);
+
+    if items.is_empty() {
+        let paragraph = Paragraph::new("The archive is empty")
+            .block(block)
+            .alignment(Alignment::Center);
+        f.render_widget(paragraph, popup_area);
+        return;
+    }
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(
             Style::default()
-                .fg(Color::Yellow)
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(app.config.theme.highlight_symbol.as_str());
+
+    f.render_stateful_widget(list, popup_area, &mut app.archive_state);
+}
+
+pub fn render_trash(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
+    let items: Vec<ListItem> = app
+        .trash
+        .iter()
+        .map(|deleted| {
+            let date = deleted.deleted_at.with_timezone(&Local);
+            ListItem::new(Line::from(vec![
+                Span::raw(deleted.todo.text.as_str()),
+                Span::styled(
+                    format!(" (deleted {})", date.format(&app.config.date_format)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let title = format!(" Recently Deleted ({}) ", items.len());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(Color::Red));
+
+    let popup_area = centered_rect(70, 70, area);
+    f.render_widget(Clear, popup_area);
+
+    if items.is_empty() {
+        let paragraph = Paragraph::new("Nothing has been deleted")
+            .block(block)
+            .alignment(Alignment::Center);
+        f.render_widget(paragraph, popup_area);
+        return;
+    }
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(app.config.theme.highlight_symbol.as_str());
+
+    f.render_stateful_widget(list, popup_area, &mut app.trash_state);
+}
+
+pub fn render_lists(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
+    let items: Vec<ListItem> = app
+        .lists
+        .iter()
+        .map(|name| {
+            if name == app.list_name() {
+                ListItem::new(Line::from(vec![
+                    Span::raw(name.as_str()),
+                    Span::styled(" (current)", Style::default().fg(Color::DarkGray)),
+                ]))
+            } else {
+                ListItem::new(name.as_str())
+            }
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Lists ")
+                .border_style(Style::default().fg(Color::Magenta)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
                 .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  Enter  - Add todo and return to normal mode"),
-        Line::from("  Esc    - Cancel and return to normal mode"),
+        )
+        .highlight_symbol(app.config.theme.highlight_symbol.as_str());
+
+    let popup_area = centered_rect(50, 50, area);
+    f.render_widget(Clear, popup_area);
+    f.render_stateful_widget(list, popup_area, &mut app.lists_state);
+}
+
+pub fn render_detail(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let Some(todo) = app.selected_todo() else {
+        return;
+    };
+
+    let label = |name: &'static str| {
+        Span::styled(format!("{:<9}", name), Style::default().fg(Color::Yellow))
+    };
+    let mut info = vec![
+        Line::from(Span::styled(
+            todo.text.as_str(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
         Line::from(""),
-        Line::from("Press ? or Esc to close this help"),
+        Line::from(vec![label("Id"), Span::raw(todo.id.to_string())]),
+        Line::from(vec![label("Status"), Span::raw(todo.status.label())]),
     ];
+    if let Some(source) = todo.source {
+        info.push(Line::from(vec![label("Source"), Span::raw(source.label())]));
+    }
+    if let Some(completed_at) = todo.completed_at {
+        let date = completed_at.with_timezone(&Local);
+        info.push(Line::from(vec![
+            label("Done on"),
+            Span::raw(date.format(&app.config.date_format).to_string()),
+        ]));
+    }
+    if let Some(note) = &todo.completion_note {
+        info.push(Line::from(vec![label("Outcome"), Span::raw(note.as_str())]));
+    }
+    if let Some(priority) = todo.priority {
+        info.push(Line::from(vec![
+            label("Priority"),
+            Span::raw(priority.label()),
+        ]));
+    }
+    if let Some(context) = &todo.context {
+        info.push(Line::from(vec![
+            label("Context"),
+            Span::raw(context.as_str()),
+        ]));
+    }
+    if !todo.tags.is_empty() {
+        let tags: Vec<String> = todo.tags.iter().map(|t| format!("#{}", t)).collect();
+        info.push(Line::from(vec![label("Tags"), Span::raw(tags.join(" "))]));
+    }
+    if let Some(due) = due_label(todo, &app.config.date_format) {
+        info.push(Line::from(vec![label("Due"), Span::raw(due)]));
+    }
+    if todo.is_recurring() {
+        let repeats = match todo.recur_until {
+            Some(until) => format!("daily until {}", until.format(&app.config.date_format)),
+            None => "daily".to_string(),
+        };
+        info.push(Line::from(vec![label("Repeats"), Span::raw(repeats)]));
+    }
+    if let Some(progress) = todo.progress {
+        info.push(Line::from(vec![
+            label("Progress"),
+            Span::raw(format!("{}%", progress)),
+        ]));
+    }
+    let tracked = todo.tracked_seconds(Utc::now());
+    if tracked > 0 {
+        info.push(Line::from(vec![
+            label("Tracked"),
+            Span::raw(format_duration(tracked)),
+        ]));
+    }
 
-    let paragraph = Paragraph::new(help_text)
+    let popup_area = centered_rect(70, 70, area);
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Details ")
+        .border_style(Style::default().fg(Color::Blue));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    // The full text wraps here, however long it is
+    let text_lines = Span::raw(todo.text.as_str())
+        .width()
+        .div_ceil(inner.width.max(1) as usize)
+        .max(1);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length((info.len() + text_lines) as u16),
+            Constraint::Min(3),
+        ])
+        .split(inner);
+
+    f.render_widget(Paragraph::new(info).wrap(Wrap { trim: false }), chunks[0]);
+
+    if matches!(app.mode, AppMode::EditNotes) {
+        render_notes_editor(f, app, chunks[1]);
+        return;
+    }
+
+    let notes = if todo.notes.is_empty() {
+        Paragraph::new(Span::styled(
+            "No notes, press e to add some",
+            Style::default().fg(Color::DarkGray),
+        ))
+    } else {
+        Paragraph::new(todo.notes.as_str()).wrap(Wrap { trim: false })
+    };
+    f.render_widget(
+        notes.block(
+            Block::default()
+                .borders(Borders::TOP)
+                .title(" Notes ")
+                .border_style(Style::default().fg(Color::DarkGray)),
+        ),
+        chunks[1],
+    );
+}
+
+fn render_notes_editor(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Notes (Ctrl-S save, Esc cancel) ")
+        .border_style(Style::default().fg(Color::Green));
+    let inner = block.inner(area);
+
+    // Scroll so the cursor row stays in view
+    let (row, col) = app.notes_editor.cursor();
+    let scroll = row.saturating_sub(inner.height.saturating_sub(1) as usize);
+
+    let lines: Vec<Line> = app
+        .notes_editor
+        .lines()
+        .iter()
+        .map(|line| Line::from(line.as_str()))
+        .collect();
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((scroll as u16, 0)),
+        area,
+    );
+
+    f.set_cursor_position((inner.x + col as u16, inner.y + (row - scroll) as u16));
+}
+
+pub fn render_duplicates(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
+    let items: Vec<ListItem> = app
+        .duplicates
+        .iter()
+        .filter_map(|ids| {
+            let first = app.todos.iter().find(|t| t.id == ids[0])?;
+            let ids: Vec<String> = ids.iter().map(|id| format!("#{}", id)).collect();
+            Some(ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{}× ", ids.len()),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::raw(first.text.as_str()),
+                Span::styled(
+                    format!(" ({})", ids.join(", ")),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])))
+        })
+        .collect();
+
+    let title = format!(" Duplicates ({}) ", items.len());
+    let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" Help ")
-                .border_style(Style::default().fg(Color::Cyan)),
+                .title(title)
+                .border_style(Style::default().fg(Color::Yellow)),
         )
-        .wrap(Wrap { trim: true })
-        .alignment(Alignment::Left);
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(app.config.theme.highlight_symbol.as_str());
 
-    let popup_area = centered_rect(60, 80, area);
+    let popup_area = centered_rect(70, 60, area);
     f.render_widget(Clear, popup_area);
-    f.render_widget(paragraph, popup_area);
+    f.render_stateful_widget(list, popup_area, &mut app.duplicates_state);
+}
+
+pub fn render_tags(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
+    let tags = app
+        .selected_todo()
+        .map(|t| t.tags.clone())
+        .unwrap_or_default();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Tags ")
+        .border_style(Style::default().fg(Color::Cyan));
+
+    let popup_area = centered_rect(40, 40, area);
+    f.render_widget(Clear, popup_area);
+
+    if tags.is_empty() {
+        let paragraph = Paragraph::new("This todo has no tags")
+            .block(block)
+            .alignment(Alignment::Center);
+        f.render_widget(paragraph, popup_area);
+        return;
+    }
+
+    let items: Vec<ListItem> = tags
+        .into_iter()
+        .map(|tag| ListItem::new(format!("#{}", tag)))
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(app.config.theme.highlight_symbol.as_str());
+
+    f.render_stateful_widget(list, popup_area, &mut app.tag_state);
 }
 
 pub fn render_status_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
@@ -116,29 +1165,157 @@ pub fn render_status_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect)
         AppMode::Normal => "NORMAL",
         AppMode::Insert => "INSERT",
         AppMode::Help => "HELP",
+        AppMode::CheatSheet => "KEYS",
+        AppMode::Confirm => "CONFIRM",
+        AppMode::Search => "SEARCH",
+        AppMode::Dashboard => "TODAY",
+        AppMode::Archive => "ARCHIVE",
+        AppMode::Detail => "DETAIL",
+        AppMode::EditNotes => "NOTES",
+        AppMode::Tags => "TAGS",
+        AppMode::Trash => "TRASH",
+        AppMode::About => "ABOUT",
+        AppMode::Lists => "LISTS",
+        AppMode::Duplicates => "DUPLICATES",
+        AppMode::Prompt => "INPUT",
     };
 
     let mode_style = match app.mode {
         AppMode::Normal => Style::default().bg(Color::Blue).fg(Color::White),
         AppMode::Insert => Style::default().bg(Color::Green).fg(Color::Black),
-        AppMode::Help => Style::default().bg(Color::Cyan).fg(Color::Black),
+        AppMode::Help | AppMode::CheatSheet => Style::default().bg(Color::Cyan).fg(Color::Black),
+        AppMode::Confirm => Style::default().bg(Color::Red).fg(Color::White),
+        AppMode::Search => Style::default().bg(Color::Yellow).fg(Color::Black),
+        AppMode::Dashboard | AppMode::Archive => {
+            Style::default().bg(Color::Magenta).fg(Color::White)
+        }
+        AppMode::Detail => Style::default().bg(Color::Blue).fg(Color::White),
+        AppMode::EditNotes => Style::default().bg(Color::Green).fg(Color::Black),
+        AppMode::Tags => Style::default().bg(Color::Cyan).fg(Color::Black),
+        AppMode::Trash => Style::default().bg(Color::Red).fg(Color::White),
+        AppMode::About => Style::default().bg(Color::Cyan).fg(Color::Black),
+        AppMode::Lists => Style::default().bg(Color::Magenta).fg(Color::White),
+        AppMode::Duplicates => Style::default().bg(Color::Yellow).fg(Color::Black),
+        AppMode::Prompt => Style::default().bg(Color::Magenta).fg(Color::White),
     };
 
     let completed_count = app.completed_count();
+    let in_progress_count = app.in_progress_count();
     let total_count = app.total_count();
 
-    let status_text = if total_count > 0 {
-        format!(
-            " {} | {}/{} completed | Press ? for help ",
-            mode_text, completed_count, total_count
-        )
-    } else {
-        format!(
-            " {} | Press 'i' to add your first todo | Press ? for help ",
-            mode_text
-        )
-    };
+    let mut segments = vec![(Segment::Mode, format!(" {} |", mode_text))];
+    segments.push((
+        Segment::Counts,
+        if total_count > 0 && in_progress_count > 0 {
+            format!(
+                " {}/{} completed, {} in progress |",
+                completed_count, total_count, in_progress_count
+            )
+        } else if total_count > 0 {
+            format!(" {}/{} completed |", completed_count, total_count)
+        } else {
+            match app.keymap.key_label(Action::Add) {
+                Some(key) => format!(" Press {} to add your first todo |", key),
+                None => " No todos yet |".to_string(),
+            }
+        },
+    ));
 
+    if let Some(message) = &app.message {
+        segments.push((Segment::Notice, format!(" {} |", message)));
+    }
+
+    if let Some(undo) = app.pending_undo(Instant::now()) {
+        segments.push((
+            Segment::Notice,
+            format!(
+                " Deleted '{}' — press {} to undo |",
+                undo.text,
+                app.keymap.keys_label(Action::UndoDelete)
+            ),
+        ));
+    }
+
+    if app.read_only {
+        segments.push((Segment::Notice, " read-only |".to_string()));
+    } else if app.dirty {
+        segments.push((Segment::Notice, " ● unsaved |".to_string()));
+    } else if let Some(last_saved) = app.last_saved {
+        segments.push((
+            Segment::Saved,
+            format!(" saved {} |", last_saved.format("%H:%M")),
+        ));
+    }
+
+    if let Some(todo) = app.tracking_todo() {
+        segments.push((
+            Segment::Activity,
+            format!(
+                " ⏱ {} {} |",
+                todo.text,
+                format_duration(todo.tracked_seconds(Utc::now()))
+            ),
+        ));
+    }
+
+    if app.sort_mode() != SortMode::Created || app.sort_direction() != SortDirection::Ascending {
+        segments.push((
+            Segment::Activity,
+            format!(
+                " sort: {} {} |",
+                app.sort_mode().label(),
+                app.sort_direction().arrow()
+            ),
+        ));
+    }
+
+    if app.overdue_view.is_some() {
+        segments.push((
+            Segment::View,
+            format!(
+                " OVERDUE ({} to leave) |",
+                app.keymap.keys_label(Action::OverdueView)
+            ),
+        ));
+    } else if app.filter != Filter::All {
+        segments.push((Segment::View, format!(" filter: {} |", app.filter.label())));
+    }
+
+    let searching = !app.search.value().trim().is_empty();
+    let query = app.search_query();
+    if !query.text.is_empty() {
+        segments.push((Segment::View, format!(" search: {} |", query.text)));
+    }
+    if let Some(tags) = query.tag_expression() {
+        segments.push((Segment::View, format!(" tags: {} |", tags)));
+    }
+    if let Some(source) = query.source {
+        segments.push((Segment::View, format!(" source: {} |", source.label())));
+    }
+    if let Some(context) = &query.context {
+        segments.push((Segment::View, format!(" context: {} |", context)));
+    }
+
+    if app.due_window != DueWindow::Any && app.overdue_view.is_none() {
+        segments.push((Segment::View, format!(" due: {} |", app.due_window.label())));
+    }
+
+    if app.filter != Filter::All || app.due_window != DueWindow::Any || searching {
+        segments.push((
+            Segment::View,
+            format!(
+                " showing {} of {} |",
+                app.visible_indices().len(),
+                total_count
+            ),
+        ));
+    }
+
+    for (key, action) in key_hints(app) {
+        segments.push((Segment::Hint, format!(" {}:{}", key, action)));
+    }
+
+    let status_text = fit_status(segments, area.width as usize, app.config.status_overflow);
     let paragraph = Paragraph::new(status_text)
         .style(mode_style)
         .alignment(Alignment::Left);
@@ -146,6 +1323,256 @@ pub fn render_status_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect)
     f.render_widget(paragraph, area);
 }
 
+/// Parts of the status bar, from the most to the least important to keep
+/// when the terminal is too narrow for all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Segment {
+    Mode,
+    Counts,
+    /// Messages, the undo offer and unsaved changes.
+    Notice,
+    /// What the list is filtered by.
+    View,
+    /// Sort order and the running timer.
+    Activity,
+    Saved,
+    Hint,
+}
+
+/// Joins the status bar `segments` into at most `width` columns. Key hints
+/// go first when space runs out; with `StatusOverflow::Drop` the other
+/// segments follow, least important first. Whatever is left and still too
+/// wide is cut with an ellipsis after the last whole word that fits; only a
+/// first word wider than the bar is cut mid-word.
+fn fit_status(
+    mut segments: Vec<(Segment, String)>,
+    width: usize,
+    overflow: StatusOverflow,
+) -> String {
+    let total = |segments: &[(Segment, String)]| -> usize {
+        segments
+            .iter()
+            .map(|(_, text)| Span::raw(text.as_str()).width())
+            .sum()
+    };
+    let lowest = match overflow {
+        StatusOverflow::Drop => Segment::Counts,
+        StatusOverflow::Truncate => Segment::Hint,
+    };
+    while total(&segments) > width {
+        // The last of the least important segments goes first
+        let Some(drop) = segments
+            .iter()
+            .enumerate()
+            .filter(|(_, (segment, _))| *segment > lowest)
+            .max_by_key(|(i, (segment, _))| (*segment, *i))
+            .map(|(i, _)| i)
+        else {
+            break;
+        };
+        segments.remove(drop);
+    }
+
+    let text: String = segments.into_iter().map(|(_, text)| text).collect();
+    if Span::raw(text.as_str()).width() <= width {
+        return text;
+    }
+    let cut: String = truncate_spans(vec![Span::raw(text.as_str())], width)
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect();
+    let kept = cut.strip_suffix('…').unwrap_or(&cut);
+    // Back up to the start of the word the cut went through
+    let words = kept.trim_end_matches(|c: char| !c.is_whitespace());
+    if text[kept.len()..].starts_with(|c: char| !c.is_whitespace()) && !words.trim().is_empty() {
+        format!("{}…", words)
+    } else {
+        cut
+    }
+}
+
+/// Title of the todo panel without the count: the configured title, or the
+/// list name with the default list shown as "Todos".
+fn list_title(app: &App) -> String {
+    match &app.config.list_title {
+        Some(title) => title.replace("{list}", app.list_name()),
+        None if app.list_name() == DEFAULT_LIST_NAME => "Todos".to_string(),
+        None => app.list_name().to_string(),
+    }
+}
+
+/// Splits `text` into lines of at most `width` columns, breaking between
+/// words where possible.
+fn wrap_words(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let needed = if line.is_empty() { 0 } else { 1 } + Span::raw(word).width();
+        if !line.is_empty() && Span::raw(line.as_str()).width() + needed > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines
+}
+
+/// Cells in the inline progress bar.
+const PROGRESS_BAR_CELLS: usize = 5;
+/// Width of the progress column: the bar, a space and up to `100%`, plus a
+/// trailing gap.
+const PROGRESS_WIDTH: usize = PROGRESS_BAR_CELLS + 6;
+
+/// A small bar such as `▰▰▰▱▱  60% `.
+fn progress_span(progress: u8) -> Span<'static> {
+    let filled = (progress as usize * PROGRESS_BAR_CELLS).div_ceil(100);
+    Span::styled(
+        format!(
+            "{}{} {:>3}% ",
+            "▰".repeat(filled),
+            "▱".repeat(PROGRESS_BAR_CELLS - filled),
+            progress
+        ),
+        Style::default().fg(Color::Green),
+    )
+}
+
+/// Width of the priority column, wide enough for the `!!!` marker.
+const PRIORITY_WIDTH: usize = 3;
+
+fn priority_span(priority: Priority) -> Span<'static> {
+    let (marker, color) = match priority {
+        Priority::Low => ("  !", Color::Blue),
+        Priority::Medium => (" !!", Color::Yellow),
+        Priority::High => ("!!!", Color::Red),
+    };
+    Span::styled(
+        marker,
+        Style::default().fg(color).add_modifier(Modifier::BOLD),
+    )
+}
+
+/// The due date in the configured format, followed by the due time if set.
+fn due_label(todo: &TodoItem, date_format: &str) -> Option<String> {
+    let date = todo.due?.format(date_format).to_string();
+    Some(match todo.due_time {
+        Some(time) => format!("{} {}", date, time.format("%H:%M")),
+        None => date,
+    })
+}
+
+/// Formats a number of seconds as `H:MM:SS`.
+fn format_duration(seconds: u64) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds % 3600) / 60,
+        seconds % 60
+    )
+}
+
+/// Actions hinted at in Normal mode, in order of importance. Their keys
+/// come from the keymap.
+const NORMAL_HINTS: [(Action, &str); 5] = [
+    (Action::Add, "add"),
+    (Action::Toggle, "toggle"),
+    (Action::Delete, "del"),
+    (Action::Help, "help"),
+    (Action::Quit, "quit"),
+];
+
+/// The most relevant keys for the current mode, in order of importance.
+fn key_hints(app: &App) -> Vec<(String, &'static str)> {
+    if let AppMode::Normal = app.mode {
+        return NORMAL_HINTS
+            .iter()
+            .filter_map(|&(action, label)| Some((app.keymap.key_label(action)?, label)))
+            .collect();
+    }
+    fixed_key_hints(&app.mode)
+        .iter()
+        .map(|&(key, label)| (key.to_string(), label))
+        .collect()
+}
+
+/// Hints for the modes whose keys aren't in the keymap.
+fn fixed_key_hints(mode: &AppMode) -> &'static [(&'static str, &'static str)] {
+    match mode {
+        AppMode::Normal => &[],
+        AppMode::Insert => &[("Enter", "save"), ("Esc", "cancel")],
+        AppMode::Help => &[("↑/↓", "scroll"), ("a", "about"), ("Esc", "close")],
+        AppMode::CheatSheet => &[("↑/↓", "scroll"), ("Esc", "close")],
+        AppMode::Confirm => &[("y", "confirm"), ("n", "cancel")],
+        AppMode::Search => &[("Enter", "apply"), ("Esc", "clear"), ("↑/↓", "history")],
+        AppMode::Dashboard => &[("any key", "continue")],
+        AppMode::Archive => &[("Enter", "restore"), ("Esc", "close")],
+        AppMode::Detail => &[("e", "edit notes"), ("Esc", "close")],
+        AppMode::EditNotes => &[("Ctrl-S", "save"), ("Esc", "cancel")],
+        AppMode::Tags => &[("d", "remove"), ("Esc", "close")],
+        AppMode::Trash => &[("Enter", "restore"), ("Esc", "close")],
+        AppMode::About => &[("Esc", "back")],
+        AppMode::Lists => &[
+            ("Enter", "open"),
+            ("n", "new"),
+            ("r", "rename"),
+            ("Esc", "close"),
+        ],
+        AppMode::Prompt => &[("Enter", "ok"), ("Esc", "cancel")],
+        AppMode::Duplicates => &[("m", "merge"), ("d", "delete extras"), ("Esc", "close")],
+    }
+}
+
+/// Bordered block used for the main panels. Dense mode drops the border
+/// (and with it the title) to free up rows on small screens.
+pub fn panel_block<'a>(dense: bool, title: impl Into<Line<'a>>, color: Color) -> Block<'a> {
+    if dense {
+        Block::default().borders(Borders::NONE)
+    } else {
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(color))
+    }
+}
+
+/// Size limits as (width, height) for popups sized with
+/// `centered_rect_clamped`.
+const HELP_MIN_SIZE: (u16, u16) = (44, 12);
+const HELP_MAX_SIZE: (u16, u16) = (72, 40);
+const CHEAT_SHEET_MIN_SIZE: (u16, u16) = (60, 12);
+const CHEAT_SHEET_MAX_SIZE: (u16, u16) = (120, 40);
+const CONFIRM_MIN_SIZE: (u16, u16) = (30, 5);
+const CONFIRM_MAX_SIZE: (u16, u16) = (60, 7);
+
+/// Like `centered_rect`, but keeps the popup between `min` and `max`
+/// (width, height) so it stays readable on very narrow or very wide
+/// terminals. The popup never exceeds `r`.
+pub fn centered_rect_clamped(
+    percent_x: u16,
+    percent_y: u16,
+    min: (u16, u16),
+    max: (u16, u16),
+    r: ratatui::layout::Rect,
+) -> ratatui::layout::Rect {
+    let clamp = |total: u16, percent: u16, min: u16, max: u16| {
+        let size = (total as u32 * percent as u32 / 100) as u16;
+        size.clamp(min, max).min(total)
+    };
+
+    let width = clamp(r.width, percent_x, min.0, max.0);
+    let height = clamp(r.height, percent_y, min.1, max.1);
+
+    ratatui::layout::Rect {
+        x: r.x + (r.width - width) / 2,
+        y: r.y + (r.height - height) / 2,
+        width,
+        height,
+    }
+}
+
 pub fn centered_rect(
     percent_x: u16,
     percent_y: u16,
@@ -183,11 +1610,14 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         .split(size);
 
     match app.mode {
-        AppMode::Insert => {
+        AppMode::Insert | AppMode::Search | AppMode::Prompt => {
             // Split main area for todos and input
             let main_chunks = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Constraint::Min(5), Constraint::Length(3)])
+                .constraints([
+                    Constraint::Min(5),
+                    Constraint::Length(if app.dense { 1 } else { 3 }),
+                ])
                 .split(chunks[0]);
 
             render_todos(f, app, main_chunks[0]);
@@ -195,7 +1625,48 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         }
         AppMode::Help => {
             render_todos(f, app, chunks[0]);
-            render_help(f, size);
+            render_help(f, app, size);
+        }
+        AppMode::CheatSheet => {
+            render_todos(f, app, chunks[0]);
+            render_cheat_sheet(f, app, size);
+        }
+        AppMode::Confirm => {
+            render_todos(f, app, chunks[0]);
+            render_confirm(f, app, size);
+        }
+        AppMode::Dashboard => {
+            render_todos(f, app, chunks[0]);
+            render_dashboard(f, app, size);
+        }
+        AppMode::Archive => {
+            render_todos(f, app, chunks[0]);
+            render_archive(f, app, size);
+        }
+        AppMode::Detail | AppMode::EditNotes => {
+            render_todos(f, app, chunks[0]);
+            render_detail(f, app, size);
+        }
+        AppMode::Tags => {
+            render_todos(f, app, chunks[0]);
+            render_tags(f, app, size);
+        }
+        AppMode::Trash => {
+            render_todos(f, app, chunks[0]);
+            render_trash(f, app, size);
+        }
+        AppMode::Lists => {
+            render_todos(f, app, chunks[0]);
+            render_lists(f, app, size);
+        }
+        AppMode::Duplicates => {
+            render_todos(f, app, chunks[0]);
+            render_duplicates(f, app, size);
+        }
+        AppMode::About => {
+            render_todos(f, app, chunks[0]);
+            render_help(f, app, size);
+            render_about(f, app, size);
         }
         AppMode::Normal => {
             render_todos(f, app, chunks[0]);
@@ -205,3 +1676,116 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     // Always render status bar
     render_status_bar(f, app, chunks[1]);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+
+    #[test]
+    fn help_popup_fills_narrow_terminal() {
+        let area = Rect::new(0, 0, 40, 24);
+        let popup = centered_rect_clamped(60, 80, HELP_MIN_SIZE, HELP_MAX_SIZE, area);
+
+        assert_eq!(popup.width, 40);
+        assert_eq!(popup.x, 0);
+        assert_eq!(popup.height, 19);
+    }
+
+    #[test]
+    fn help_popup_is_capped_on_wide_terminal() {
+        let area = Rect::new(0, 0, 200, 60);
+        let popup = centered_rect_clamped(60, 80, HELP_MIN_SIZE, HELP_MAX_SIZE, area);
+
+        assert_eq!(popup.width, HELP_MAX_SIZE.0);
+        assert_eq!(popup.height, HELP_MAX_SIZE.1);
+        assert_eq!(popup.x, (200 - HELP_MAX_SIZE.0) / 2);
+        assert_eq!(popup.y, (60 - HELP_MAX_SIZE.1) / 2);
+    }
+
+    #[test]
+    fn right_to_left_text_is_drawn_in_display_order() {
+        // "shalom (abc 12)" in Hebrew letters, with a Latin word and a number
+        let line = bidi::layout("שלום (abc 12)");
+        assert!(line.rtl);
+        assert_eq!(line.text, "(abc 12) םולש");
+        // The first letter is read first, so it is drawn rightmost
+        assert_eq!(line.columns[0], 12);
+        assert_eq!(line.columns[6], 1);
+
+        let mixed = bidi::layout("buy חלב now");
+        assert!(!mixed.rtl);
+        assert_eq!(mixed.text, "buy בלח now");
+        assert_eq!(bidi::display("plain"), "plain");
+    }
+
+    #[test]
+    fn completion_sweep_strikes_through_the_start_of_the_text() {
+        let done = Style::default().add_modifier(Modifier::CROSSED_OUT);
+        let spans = sweep("write 日本".into(), 0.5, done);
+        assert_eq!(spans[0].content, "write");
+        assert_eq!(spans[0].style, done);
+        assert_eq!(spans[1].content, " 日本");
+
+        // Wide characters are struck whole
+        let spans = sweep("日本".into(), 0.3, done);
+        assert_eq!(spans[0].content, "日");
+    }
+
+    #[test]
+    fn narrow_status_bar_drops_the_least_important_parts_first() {
+        let segments = || {
+            vec![
+                (Segment::Mode, " NORMAL |".to_string()),
+                (Segment::Counts, " 1/2 completed |".to_string()),
+                (Segment::Notice, " Theme reloaded |".to_string()),
+                (Segment::Saved, " saved 09:30 |".to_string()),
+                (Segment::Hint, " i:add".to_string()),
+            ]
+        };
+        let fit = |width, overflow| fit_status(segments(), width, overflow);
+
+        assert_eq!(
+            fit(80, StatusOverflow::Drop),
+            " NORMAL | 1/2 completed | Theme reloaded | saved 09:30 | i:add"
+        );
+        assert_eq!(
+            fit(42, StatusOverflow::Drop),
+            " NORMAL | 1/2 completed | Theme reloaded |"
+        );
+        assert_eq!(fit(20, StatusOverflow::Drop), " NORMAL | 1/2 …");
+        assert_eq!(fit(5, StatusOverflow::Drop), " NOR…");
+        assert_eq!(
+            fit(42, StatusOverflow::Truncate),
+            " NORMAL | 1/2 completed | Theme reloaded …"
+        );
+    }
+
+    #[test]
+    fn very_long_text_is_cut_to_the_row_width() {
+        let text = "x".repeat(1000);
+        let line = columns(
+            vec![Span::raw("[ ] "), Span::raw(text.as_str())],
+            vec![Span::raw(" due 2026-10-17")],
+            60,
+        );
+
+        assert_eq!(line.width(), 60);
+        let rendered: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+        assert!(rendered.contains("x… "));
+        assert!(rendered.ends_with(" due 2026-10-17"));
+    }
+
+    #[test]
+    fn confirm_popup_stays_readable_at_both_extremes() {
+        let narrow = Rect::new(0, 0, 40, 24);
+        let popup = centered_rect_clamped(50, 20, CONFIRM_MIN_SIZE, CONFIRM_MAX_SIZE, narrow);
+        assert_eq!(popup.width, CONFIRM_MIN_SIZE.0);
+        assert_eq!(popup.height, CONFIRM_MIN_SIZE.1);
+
+        let wide = Rect::new(0, 0, 200, 60);
+        let popup = centered_rect_clamped(50, 20, CONFIRM_MIN_SIZE, CONFIRM_MAX_SIZE, wide);
+        assert_eq!(popup.width, CONFIRM_MAX_SIZE.0);
+        assert_eq!(popup.height, CONFIRM_MAX_SIZE.1);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::logger;
+use color_eyre::Result;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+/// How long a file has to stay quiet before its changes are picked up, so
+/// a burst of writes (or a sync tool replacing the file) reloads only once.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watches the data directory for changes made by other processes.
+///
+/// The directory is watched rather than the file itself because editors and
+/// sync tools often replace files instead of writing to them in place.
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    /// When the watched file last changed, until that change is reported.
+    last_change: Option<Instant>,
+}
+
+impl std::fmt::Debug for FileWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileWatcher")
+            .field("last_change", &self.last_change)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FileWatcher {
+    pub fn new(dir: &Path) -> Result<Self> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        logger::debug!("watching {} for changes", dir.display());
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            last_change: None,
+        })
+    }
+
+    /// Returns whether `file` changed and has since been quiet for the
+    /// debounce period.
+    pub fn poll(&mut self, file: &Path, now: Instant) -> bool {
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) if !event.kind.is_access() => {
+                    if event
+                        .paths
+                        .iter()
+                        .any(|p| p.file_name() == file.file_name())
+                    {
+                        self.last_change = Some(now);
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => logger::debug!("file watcher error: {}", err),
+            }
+        }
+
+        match self.last_change {
+            Some(at) if now - at >= DEBOUNCE => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}